@@ -0,0 +1,33 @@
+//! Parses and compares arbitrary version strings, as a hostile registry
+//! could return them, checking that nothing panics and that the orderings
+//! stay consistent.
+//!
+//! Run with `cargo +nightly fuzz run version` from this directory.
+
+#![no_main]
+
+use std::cmp::Ordering;
+
+use libfuzzer_sys::fuzz_target;
+use moz_cli_version_check::{normalize_version, VersionOrdering, VersionScheme};
+
+fuzz_target!(|input: (&str, &str)| {
+    let (a, b) = input;
+
+    if let Ok(normalized) = normalize_version(a) {
+        assert!(normalized.len() <= 4 * a.len() + 8);
+        assert_eq!(normalize_version(&normalized).as_ref(), Ok(&normalized));
+    }
+
+    for scheme in [
+        VersionScheme::Semver,
+        VersionScheme::CalVer,
+        VersionScheme::Numeric,
+    ] {
+        assert_eq!(scheme.compare(a, a), Ordering::Equal);
+        assert_eq!(scheme.compare(a, b), scheme.compare(b, a).reverse());
+        let _ = scheme.validate(a);
+        let _ = scheme.is_prerelease(a);
+        let _ = scheme.bump(a, b);
+    }
+});
@@ -0,0 +1,53 @@
+//! `cargo moz-check`: runs `moz-version-check` the way cargo runs its
+//! subcommands. On its own, it checks every crate `cargo install` put on the
+//! machine; any arguments are passed on, as in `cargo moz-check cache show`.
+
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+fn main() -> ExitCode {
+    // Cargo passes the subcommand's name first.
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "moz-check") {
+        args.remove(0);
+    }
+    if args.iter().all(|arg| arg.starts_with('-')) && !is_info(&args) {
+        args.splice(0..0, ["check".to_string(), "--installed".to_string()]);
+    }
+
+    let mut command = Command::new(moz_version_check());
+    command.args(&args);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = command.exec();
+        eprintln!("cargo moz-check: could not run moz-version-check: {}", err);
+        ExitCode::FAILURE
+    }
+    #[cfg(not(unix))]
+    match command.status() {
+        Ok(status) => ExitCode::from(status.code().unwrap_or(1).clamp(0, 255) as u8),
+        Err(err) => {
+            eprintln!("cargo moz-check: could not run moz-version-check: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Whether `args` only ask for help or the version, which go to
+/// `moz-version-check` itself rather than to the audit.
+fn is_info(args: &[String]) -> bool {
+    args.iter()
+        .any(|arg| matches!(arg.as_str(), "-h" | "--help" | "-V" | "--version"))
+}
+
+/// `moz-version-check` next to this executable, where `cargo install` puts
+/// both, or else wherever the `PATH` has it.
+fn moz_version_check() -> PathBuf {
+    let name = format!("moz-version-check{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(&name)))
+        .filter(|sibling| sibling.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
@@ -0,0 +1,45 @@
+//! Durations given on the command line, such as `30m`, `12h`, `1w` or
+//! `1d12h`.
+
+use std::time::Duration;
+
+/// Parses a duration made of numbers each followed by a unit: `s`, `m`,
+/// `h`, `d` or `w`. A bare `0` is zero.
+pub(crate) fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    if text == "0" {
+        return Ok(Duration::ZERO);
+    }
+    let invalid = || {
+        format!(
+            "invalid duration {:?}: expected e.g. 30m, 12h, 7d or 1w",
+            text
+        )
+    };
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    let mut seconds: u64 = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let count: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        let unit_seconds = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 7 * 86400,
+            _ => return Err(invalid()),
+        };
+        seconds = count
+            .checked_mul(unit_seconds)
+            .and_then(|more| seconds.checked_add(more))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+    Ok(Duration::from_secs(seconds))
+}
@@ -0,0 +1,43 @@
+//! The crates installed with `cargo install`, as `cargo install --list`
+//! reports them.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The name and version of every crate `cargo install` put on the machine.
+pub(crate) fn installed_crates() -> Result<Vec<(String, String)>, String> {
+    let output = Command::new("cargo")
+        .args(["install", "--list"])
+        .output()
+        .map_err(|err| format!("could not run cargo: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "`cargo install --list` exited with {}",
+            output.status
+        ));
+    }
+    Ok(parse_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Looks the executable `name` up on the `PATH`, the way a shell would.
+pub(crate) fn executable(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Reads lines like `searchfox-cli v0.3.0:` or `tool v1.0.0 (/path):`,
+/// skipping the indented names of the binaries each crate installed.
+fn parse_list(list: &str) -> Vec<(String, String)> {
+    list.lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            let version = words.next()?.trim_end_matches(':').strip_prefix('v')?;
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect()
+}
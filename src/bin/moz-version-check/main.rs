@@ -0,0 +1,1140 @@
+//! `moz-version-check`: the library's checks from the command line, for
+//! shell scripts and for seeing what the checker does outside a host tool.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command as Process, ExitCode};
+use std::time::Duration;
+
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use moz_cli_version_check::{
+    cached_tools, checks_disabled, clear_cache, clear_cached_tool, config_path, diagnose,
+    format_span, pin_tool, pinned_tools, prefetch, snooze_tool, unpin_tool, validate_config,
+    CachedTool, ConfigLayer, CratesIo, Diagnostic, FileManifest, Health, InstallMethod, Release,
+    SelfUpdateError, SelfUpdateOutcome, Source, UpdateMethod, UpdateStatus, VersionChecker,
+    VersionReq,
+};
+use serde_json::{json, Value};
+
+mod duration;
+mod installed;
+mod output;
+
+use duration::parse_duration;
+use output::{format_elapsed, now, table, Output};
+
+/// How long to wait for a lookup, which gives up on its own after 5 seconds.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Exit codes of `check`, for scripts to branch on without parsing the
+// output. It exits with 0 when up to date, and with 1 or 2 when it could not
+// run at all, as for bad arguments.
+const UPDATE_AVAILABLE: u8 = 10;
+const CHECK_FAILED: u8 = 20;
+const DISABLED: u8 = 30;
+
+fn command() -> Command {
+    Command::new("moz-version-check")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Check Mozilla CLI tools for newer releases")
+        .subcommand_required(true)
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print results as JSON, one object per line"),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check whether a tool has a newer release")
+                .arg(
+                    Arg::new("tool")
+                        .required_unless_present_any(["all", "installed"])
+                        .help("Name of the tool's crate"),
+                )
+                .arg(
+                    Arg::new("version")
+                        .required_unless_present_any(["all", "installed"])
+                        .help("Version of the tool installed"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("tool")
+                        .help("Check every tool in the cache, at the version last seen running"),
+                )
+                .arg(
+                    Arg::new("installed")
+                        .long("installed")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["tool", "all"])
+                        .help("Check every crate listed by `cargo install --list`"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .requires("every")
+                        .help("Look every tool up, even those checked recently"),
+                )
+                .group(ArgGroup::new("every").args(["all", "installed"])),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Check the tools in the cache on a schedule, printing what changes")
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .default_value("1h")
+                        .value_parser(parse_duration)
+                        .help("How long between checks, such as 30m, 1h or 1d"),
+                ),
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("Show or reset what the cache records")
+                .subcommand(
+                    Command::new("show").about("List what the cache records about each tool"),
+                )
+                .subcommand(
+                    Command::new("clear")
+                        .about("Forget what the cache records, so tools check again")
+                        .arg(Arg::new("tool").help("Only forget about this tool")),
+                ),
+        )
+        .subcommand(
+            Command::new("pin")
+                .about("Only offer updates of a tool to the versions given, in every tool")
+                .arg(Arg::new("tool").help("Name of the tool's crate; list pins without it"))
+                .arg(
+                    Arg::new("requirement")
+                        .requires("tool")
+                        .required(false)
+                        .help("Versions to stay on, as a cargo version requirement such as ^1"),
+                ),
+        )
+        .subcommand(
+            Command::new("unpin")
+                .about("Offer every update of a tool again")
+                .arg(
+                    Arg::new("tool")
+                        .required(true)
+                        .help("Name of the tool's crate"),
+                ),
+        )
+        .subcommand(
+            Command::new("snooze")
+                .about("Silence a tool's update notices for a while")
+                .arg(
+                    Arg::new("tool")
+                        .required(true)
+                        .help("Name of the tool's crate"),
+                )
+                .arg(
+                    Arg::new("duration")
+                        .default_value("1w")
+                        .value_parser(parse_duration)
+                        .help("How long, such as 12h, 3d or 1w; 0 to stop snoozing"),
+                ),
+        )
+        .subcommand(
+            Command::new("notes")
+                .about("Show the release notes of the versions newer than the one installed")
+                .arg(
+                    Arg::new("tool")
+                        .required(true)
+                        .help("Name of the tool's crate"),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("Show the versions newer than this one instead"),
+                ),
+        )
+        .subcommand(
+            Command::new("export-manifest")
+                .about("Write the latest releases of tools to a manifest, for offline machines")
+                .arg(
+                    Arg::new("tools")
+                        .long("tools")
+                        .required(true)
+                        .value_delimiter(',')
+                        .help("Names of the tools' crates, separated by commas"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Where to write the manifest"),
+                ),
+        )
+        .subcommand(
+            Command::new("prefetch")
+                .about("Cache the latest releases of tools, so their first runs don't wait")
+                .arg(
+                    Arg::new("tools")
+                        .long("tools")
+                        .required(true)
+                        .value_delimiter(',')
+                        .help("Names of the tools' crates, separated by commas"),
+                ),
+        )
+        .subcommand(
+            Command::new("policy")
+                .about("Work with configuration files and fleet policies")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("validate")
+                        .about("Report what is wrong in a configuration, and the result")
+                        .arg(
+                            Arg::new("path")
+                                .required(true)
+                                .value_parser(clap::value_parser!(PathBuf))
+                                .help("The file to check"),
+                        )
+                        .arg(
+                            Arg::new("user")
+                                .long("user")
+                                .action(ArgAction::SetTrue)
+                                .help("Check it as a user's configuration rather than a policy"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Try each step of an update check, to see why notices show or not")
+                .arg(
+                    Arg::new("tool")
+                        .default_value(env!("CARGO_PKG_NAME"))
+                        .help("Name of the tool's crate, to look up its cached state"),
+                ),
+        )
+        .subcommand(
+            Command::new("update")
+                .about("Update a tool the way it was installed")
+                .arg(
+                    Arg::new("tool")
+                        .required(true)
+                        .help("Name of the tool's crate"),
+                )
+                .arg(
+                    Arg::new("bin")
+                        .long("bin")
+                        .help("Name of the tool's executable, when not the crate's"),
+                ),
+        )
+        .subcommand(Command::new("self-update").about("Update moz-version-check itself"))
+}
+
+fn main() -> ExitCode {
+    let matches = command().get_matches();
+    let output = Output::new(matches.get_flag("json"));
+    match matches.subcommand() {
+        Some(("check", _)) if checks_disabled() => {
+            output.print(
+                "Update checks are disabled by MOZTOOLS_UPDATE_CHECK=0.",
+                json!({ "status": "disabled" }),
+            );
+            ExitCode::from(DISABLED)
+        }
+        Some(("check", matches)) if matches.get_flag("all") => {
+            let tools = cached_tools()
+                .into_iter()
+                .filter_map(|tool| Some((tool.tool, tool.running?)))
+                .collect();
+            run_check_every(output, tools, matches.get_flag("force"))
+        }
+        Some(("check", matches)) if matches.get_flag("installed") => {
+            match installed::installed_crates() {
+                Ok(tools) => run_check_every(output, tools, matches.get_flag("force")),
+                Err(err) => {
+                    output.error(format!("Could not list the installed crates: {}", err));
+                    ExitCode::from(CHECK_FAILED)
+                }
+            }
+        }
+        Some(("check", matches)) => run_check(output, matches),
+        Some(("watch", _)) if checks_disabled() => {
+            output.error("Update checks are disabled by MOZTOOLS_UPDATE_CHECK=0.");
+            ExitCode::from(DISABLED)
+        }
+        Some(("watch", matches)) => run_watch(output, matches),
+        Some(("cache", matches)) => match matches.subcommand() {
+            Some(("clear", matches)) => run_cache_clear(output, matches),
+            _ => run_cache_show(output),
+        },
+        Some(("pin", matches)) => run_pin(output, matches),
+        Some(("unpin", matches)) => run_unpin(output, matches),
+        Some(("snooze", matches)) => run_snooze(output, matches),
+        Some(("notes", matches)) => run_notes(output, matches),
+        Some(("export-manifest", matches)) => run_export_manifest(output, matches),
+        Some(("prefetch", _)) if checks_disabled() => {
+            output.error("Update checks are disabled by MOZTOOLS_UPDATE_CHECK=0.");
+            ExitCode::from(DISABLED)
+        }
+        Some(("prefetch", matches)) => run_prefetch(output, matches),
+        Some(("policy", matches)) => match matches.subcommand() {
+            Some(("validate", matches)) => run_policy_validate(output, matches),
+            _ => ExitCode::FAILURE,
+        },
+        Some(("doctor", matches)) => run_doctor(output, matches),
+        Some(("update", matches)) => run_update(output, matches),
+        Some(("self-update", _)) => run_self_update(output),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+/// Looks the latest release of `tool` up now, whatever the cache says.
+fn check(tool: &str, version: &str) -> Option<UpdateStatus> {
+    let checker = VersionChecker::with_check_interval(tool, version, Duration::ZERO);
+    checker.check_async();
+    checker.wait_for_status(LOOKUP_TIMEOUT)
+}
+
+/// The `--json` object for the outcome of checking `tool`.
+fn status_value(tool: &str, version: &str, status: Option<&UpdateStatus>) -> Value {
+    let mut value = json!({
+        "tool": tool,
+        "current": version,
+        "status": "failed",
+        "latest": null,
+    });
+    match status {
+        Some(UpdateStatus::Available(update)) => {
+            value["status"] = json!("update-available");
+            value["latest"] = json!(update.latest);
+            value["bump"] = json!(update.bump.as_str());
+            value["url"] = json!(update.url);
+            value["published_at"] = json!(update.published_at);
+        }
+        Some(UpdateStatus::UpToDate) => value["status"] = json!("up-to-date"),
+        Some(UpdateStatus::AheadOfRegistry { latest, .. }) => {
+            value["status"] = json!("ahead");
+            value["latest"] = json!(latest);
+        }
+        None => {}
+    }
+    value
+}
+
+fn run_check(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tool = matches.get_one::<String>("tool").expect("required");
+    let version = matches.get_one::<String>("version").expect("required");
+    let status = check(tool, version);
+    let value = status_value(tool, version, status.as_ref());
+    let (text, code) = match status {
+        Some(UpdateStatus::Available(update)) => (
+            format!(
+                "{} {} is available (current: {}).",
+                update.tool, update.latest, update.current
+            ),
+            ExitCode::from(UPDATE_AVAILABLE),
+        ),
+        Some(UpdateStatus::UpToDate) => (
+            format!("{} {} is up to date.", tool, version),
+            ExitCode::SUCCESS,
+        ),
+        Some(UpdateStatus::AheadOfRegistry { current, latest }) => (
+            format!(
+                "{} {} is newer than its latest release, {}.",
+                tool, current, latest
+            ),
+            ExitCode::SUCCESS,
+        ),
+        None => {
+            output.print_failure(
+                format!("Could not find the latest release of {}.", tool),
+                value,
+            );
+            return ExitCode::from(CHECK_FAILED);
+        }
+    };
+    output.print(text, value);
+    code
+}
+
+/// Checks each of `tools`, given with the version installed, all at once.
+/// Tools checked within their interval are answered from the cache, unless
+/// `force` is set.
+fn check_every(tools: &[(String, String)], force: bool) -> Vec<Option<UpdateStatus>> {
+    let checkers: Vec<VersionChecker> = tools
+        .iter()
+        .map(|(tool, running)| match force {
+            true => VersionChecker::with_check_interval(tool, running, Duration::ZERO),
+            false => VersionChecker::new(tool, running),
+        })
+        .collect();
+    for checker in &checkers {
+        checker.check_async();
+    }
+    checkers
+        .iter()
+        .map(|checker| checker.wait_for_status(LOOKUP_TIMEOUT))
+        .collect()
+}
+
+/// The latest version a check found, if worth showing, and what it
+/// concluded, for tables.
+fn describe(status: Option<&UpdateStatus>) -> (String, &'static str) {
+    match status {
+        Some(UpdateStatus::Available(update)) => (update.latest.clone(), "update available"),
+        Some(UpdateStatus::UpToDate) => (String::new(), "up to date"),
+        Some(UpdateStatus::AheadOfRegistry { latest, .. }) => (latest.clone(), "newer than latest"),
+        None => (String::new(), "check failed"),
+    }
+}
+
+/// Checks each of `tools`, given with the version installed, all at once,
+/// and prints a table of the results with a summary.
+fn run_check_every(output: Output, tools: Vec<(String, String)>, force: bool) -> ExitCode {
+    if tools.is_empty() {
+        output.print("No tool to check.", json!({ "tools": [] }));
+        return ExitCode::SUCCESS;
+    }
+    let statuses = check_every(&tools, force);
+
+    let mut rows = vec![["TOOL", "CURRENT", "LATEST", "STATUS"].map(String::from)];
+    let mut values = Vec::new();
+    let (mut updates, mut failed) = (0, 0);
+    for ((tool, current), status) in tools.iter().zip(&statuses) {
+        values.push(status_value(tool, current, status.as_ref()));
+        match status {
+            Some(UpdateStatus::Available(_)) => updates += 1,
+            None => failed += 1,
+            Some(_) => {}
+        }
+        let (latest, described) = describe(status.as_ref());
+        rows.push([tool.clone(), current.clone(), latest, described.to_string()]);
+    }
+    let summary = match updates {
+        0 => "No tool has an update.".to_string(),
+        1 => "1 tool has an update.".to_string(),
+        n => format!("{} tools have updates.", n),
+    };
+    output.print(
+        format!("{}\n\n{}", table(&rows), summary),
+        json!({ "tools": values }),
+    );
+    if failed > 0 {
+        ExitCode::from(CHECK_FAILED)
+    } else if updates > 0 {
+        ExitCode::from(UPDATE_AVAILABLE)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Checks every tool in the cache each `interval`, at the version last seen
+/// running, and prints those whose outcome changed since the round before;
+/// all of them in the first. Runs until interrupted.
+fn run_watch(output: Output, matches: &ArgMatches) -> ExitCode {
+    let interval = *matches.get_one::<Duration>("interval").expect("defaulted");
+    if interval.is_zero() {
+        output.error("The interval must be longer than 0.");
+        return ExitCode::FAILURE;
+    }
+    output.note(format!(
+        "Checking the tools in the cache every {}; press Ctrl-C to stop.",
+        format_span(interval.as_secs())
+    ));
+    let mut seen: HashMap<String, (String, Option<UpdateStatus>)> = HashMap::new();
+    loop {
+        let tools: Vec<(String, String)> = cached_tools()
+            .into_iter()
+            .filter_map(|tool| Some((tool.tool, tool.running?)))
+            .collect();
+        let statuses = check_every(&tools, true);
+        let checked_at = now();
+        for ((tool, current), status) in tools.into_iter().zip(statuses) {
+            let before = seen.get(&tool);
+            if before == Some(&(current.clone(), status.clone())) {
+                continue;
+            }
+            let (latest, described) = describe(status.as_ref());
+            let mut text = format!("{} {}: {}", tool, current, described);
+            if !latest.is_empty() {
+                text.push_str(&format!(" ({})", latest));
+            }
+            if let Some((_, before)) = before {
+                text.push_str(&format!(", was {}", describe(before.as_ref()).1));
+            }
+            let mut value = status_value(&tool, &current, status.as_ref());
+            value["checked_at"] = json!(checked_at);
+            output.print(text, value);
+            seen.insert(tool, (current, status));
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Prints a table of the cached tools, one per line.
+fn run_cache_show(output: Output) -> ExitCode {
+    let tools = cached_tools();
+    let now = now();
+    let value = json!({
+        "tools": tools
+            .iter()
+            .map(|tool| json!({
+                "tool": tool.tool,
+                "running": tool.running,
+                "latest": tool.latest,
+                "last_check": tool.last_check,
+                "outcome": outcome(tool).replace(' ', "-"),
+                "snoozed_until": tool.snoozed_until,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    if tools.is_empty() {
+        output.print("The cache is empty.", value);
+        return ExitCode::SUCCESS;
+    }
+    let mut rows = vec![["TOOL", "RUNNING", "LATEST", "CHECKED", "OUTCOME"].map(String::from)];
+    rows.extend(tools.iter().map(|tool| {
+        [
+            tool.tool.clone(),
+            tool.running.clone().unwrap_or_else(|| "-".to_string()),
+            tool.latest.clone().unwrap_or_else(|| "-".to_string()),
+            tool.last_check
+                .map(|time| format_elapsed(now.saturating_sub(time)))
+                .unwrap_or_else(|| "never".to_string()),
+            outcome(tool).to_string(),
+        ]
+    }));
+    output.print(table(&rows), value);
+    ExitCode::SUCCESS
+}
+
+/// What the last check concluded, as far as the cache tells.
+fn outcome(tool: &CachedTool) -> &'static str {
+    if tool.latest.is_none() {
+        "no release found"
+    } else if tool.update_available {
+        "update available"
+    } else if tool.ahead {
+        "newer than latest"
+    } else {
+        "up to date"
+    }
+}
+
+fn run_cache_clear(output: Output, matches: &ArgMatches) -> ExitCode {
+    match matches.get_one::<String>("tool") {
+        Some(tool) => {
+            let cleared = clear_cached_tool(tool);
+            let text = if cleared {
+                format!("Forgot what was cached about {}.", tool)
+            } else {
+                format!("Nothing was cached about {}.", tool)
+            };
+            output.print(text, json!({ "tool": tool, "cleared": cleared }));
+        }
+        None => {
+            clear_cache();
+            output.print(
+                "Cleared the cache.",
+                json!({ "tool": null, "cleared": true }),
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Pins a tool in the user's configuration, or lists the pins.
+fn run_pin(output: Output, matches: &ArgMatches) -> ExitCode {
+    let Some(tool) = matches.get_one::<String>("tool") else {
+        let pinned = pinned_tools();
+        let value = json!({
+            "pins": pinned
+                .iter()
+                .map(|(tool, pin)| json!({ "tool": tool, "pin": pin.to_string() }))
+                .collect::<Vec<_>>(),
+        });
+        if pinned.is_empty() {
+            output.print("No tool is pinned.", value);
+        } else {
+            let mut rows = vec![["TOOL", "PIN"].map(String::from)];
+            rows.extend(
+                pinned
+                    .iter()
+                    .map(|(tool, pin)| [tool.clone(), pin.to_string()]),
+            );
+            output.print(table(&rows), value);
+        }
+        return ExitCode::SUCCESS;
+    };
+    let Some(requirement) = matches.get_one::<String>("requirement") else {
+        output.error("A version requirement is needed to pin a tool, such as ^1.");
+        return ExitCode::FAILURE;
+    };
+    let requirement = match VersionReq::parse(requirement) {
+        Ok(requirement) => requirement,
+        Err(err) => {
+            output.error(format!(
+                "Invalid version requirement {}: {}",
+                requirement, err
+            ));
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = pin_tool(tool, &requirement) {
+        output.error(format!("Could not update {}: {}", config_display(), err));
+        return ExitCode::FAILURE;
+    }
+    output.print(
+        format!("Pinned {} to {}.", tool, requirement),
+        json!({ "tool": tool, "pin": requirement.to_string() }),
+    );
+    ExitCode::SUCCESS
+}
+
+fn run_unpin(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tool = matches.get_one::<String>("tool").expect("required");
+    match unpin_tool(tool) {
+        Ok(unpinned) => {
+            let text = if unpinned {
+                format!("Unpinned {}.", tool)
+            } else {
+                format!("{} was not pinned.", tool)
+            };
+            output.print(text, json!({ "tool": tool, "unpinned": unpinned }));
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            output.error(format!("Could not update {}: {}", config_display(), err));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_snooze(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tool = matches.get_one::<String>("tool").expect("required");
+    let duration = *matches.get_one::<Duration>("duration").expect("defaulted");
+    snooze_tool(tool, duration);
+    if duration.is_zero() {
+        output.print(
+            format!("The notices of {} are no longer snoozed.", tool),
+            json!({ "tool": tool, "snoozed_until": null }),
+        );
+    } else {
+        output.print(
+            format!(
+                "Snoozed the notices of {} for {}.",
+                tool,
+                format_span(duration.as_secs())
+            ),
+            json!({ "tool": tool, "snoozed_until": now() + duration.as_secs() }),
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// The version of `tool` installed: the one last seen running, or else the
+/// one `cargo install` installed.
+fn installed_version(tool: &str) -> Option<String> {
+    let cached = cached_tools()
+        .into_iter()
+        .find(|cached| cached.tool == tool)
+        .and_then(|cached| cached.running);
+    cached.or_else(|| {
+        installed::installed_crates()
+            .ok()?
+            .into_iter()
+            .find(|(name, _)| name == tool)
+            .map(|(_, version)| version)
+    })
+}
+
+/// Prints the release notes of each version of a tool newer than the one
+/// installed, newest first, from its GitHub releases when crates.io says
+/// where its repository is.
+fn run_notes(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tool = matches.get_one::<String>("tool").expect("required");
+    let since = match matches.get_one::<String>("since") {
+        Some(since) => since.clone(),
+        None => match installed_version(tool) {
+            Some(installed) => installed,
+            None => {
+                output.error(format!(
+                    "Could not tell which version of {} is installed; pass --since.",
+                    tool
+                ));
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let releases = match CratesIo::new().releases_since(tool, &since) {
+        Ok(releases) => releases,
+        Err(err) => {
+            output.error(format!("Could not list the releases of {}: {}", tool, err));
+            return ExitCode::from(CHECK_FAILED);
+        }
+    };
+    let value = json!({
+        "tool": tool,
+        "since": since,
+        "releases": releases
+            .iter()
+            .map(|release| json!({
+                "version": release.version,
+                "url": release.url,
+                "published_at": release.published_at,
+                "notes": release.notes,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    if releases.is_empty() {
+        output.print(
+            format!("No release of {} is newer than {}.", tool, since),
+            value,
+        );
+        return ExitCode::SUCCESS;
+    }
+    let now = now();
+    let sections: Vec<String> = releases
+        .iter()
+        .map(|release| {
+            let mut section = format!("{} {}", tool, release.version);
+            if let Some(published_at) = release.published_at {
+                section.push_str(&format!(
+                    ", released {}",
+                    format_elapsed(now.saturating_sub(published_at))
+                ));
+            }
+            if let Some(url) = &release.url {
+                section.push_str(&format!("\n{}", url));
+            }
+            let notes = release.notes.as_deref().unwrap_or("No release notes.");
+            section.push_str(&format!("\n\n{}", notes));
+            section
+        })
+        .collect();
+    output.print(sections.join("\n\n"), value);
+    ExitCode::SUCCESS
+}
+
+/// The names given to `--tools`, separated by commas.
+fn tool_list(matches: &ArgMatches) -> Vec<String> {
+    matches
+        .get_many::<String>("tools")
+        .expect("required")
+        .map(|tool| tool.trim().to_string())
+        .filter(|tool| !tool.is_empty())
+        .collect()
+}
+
+/// Runs `look_up` for each of `tools`, all at once, and returns what each
+/// found in the same order.
+fn look_up_every(
+    tools: &[String],
+    look_up: impl Fn(&str) -> Option<Release> + Sync,
+) -> Vec<(String, Option<Release>)> {
+    let look_up = &look_up;
+    std::thread::scope(|scope| {
+        let lookups: Vec<_> = tools
+            .iter()
+            .map(|tool| scope.spawn(move || look_up(tool)))
+            .collect();
+        tools
+            .iter()
+            .cloned()
+            .zip(
+                lookups
+                    .into_iter()
+                    .map(|lookup| lookup.join().ok().flatten()),
+            )
+            .collect()
+    })
+}
+
+/// The tools `look_up_every` found no release of.
+fn not_found(found: &[(String, Option<Release>)]) -> Vec<&str> {
+    found
+        .iter()
+        .filter(|(_, release)| release.is_none())
+        .map(|(tool, _)| tool.as_str())
+        .collect()
+}
+
+/// Looks each tool up on crates.io, all at once, and writes what was found
+/// as a manifest for [`FileManifest`].
+fn run_export_manifest(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tools = tool_list(matches);
+    let out = matches.get_one::<PathBuf>("out").expect("required");
+
+    let found = look_up_every(&tools, |tool| CratesIo::new().latest_release(tool));
+    let failed = not_found(&found);
+    let releases: Vec<(String, Release)> = found
+        .iter()
+        .filter_map(|(tool, release)| Some((tool.clone(), release.clone()?)))
+        .collect();
+
+    if releases.is_empty() {
+        output.error(format!(
+            "Could not find the latest release of {}.",
+            failed.join(", ")
+        ));
+        return ExitCode::from(CHECK_FAILED);
+    }
+    if let Err(err) = std::fs::write(out, FileManifest::render(&releases)) {
+        output.error(format!("Could not write {}: {}", out.display(), err));
+        return ExitCode::FAILURE;
+    }
+    let value = json!({
+        "out": out.display().to_string(),
+        "tools": releases
+            .iter()
+            .map(|(tool, release)| json!({ "tool": tool, "version": release.version }))
+            .collect::<Vec<_>>(),
+        "failed": failed,
+    });
+    let written: Vec<String> = releases
+        .iter()
+        .map(|(tool, release)| format!("{} {}", tool, release.version))
+        .collect();
+    let mut text = format!("Wrote {} to {}.", written.join(", "), out.display());
+    if failed.is_empty() {
+        output.print(text, value);
+        return ExitCode::SUCCESS;
+    }
+    text.push_str(&format!(
+        "\nCould not find the latest release of {}.",
+        failed.join(", ")
+    ));
+    output.print_failure(text, value);
+    ExitCode::from(CHECK_FAILED)
+}
+
+/// Looks each tool up on crates.io, all at once, and caches what was found,
+/// so that their first runs don't wait for a lookup.
+fn run_prefetch(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tools = tool_list(matches);
+    let found = look_up_every(&tools, |tool| prefetch(tool, &CratesIo::new()));
+    let failed = not_found(&found);
+
+    let mut rows = vec![["TOOL", "LATEST"].map(String::from)];
+    rows.extend(found.iter().map(|(tool, release)| {
+        let latest = match release {
+            Some(release) => release.version.clone(),
+            None => "not found".to_string(),
+        };
+        [tool.clone(), latest]
+    }));
+    let value = json!({
+        "tools": found
+            .iter()
+            .map(|(tool, release)| json!({
+                "tool": tool,
+                "latest": release.as_ref().map(|release| &release.version),
+            }))
+            .collect::<Vec<_>>(),
+        "failed": failed,
+    });
+    if failed.is_empty() {
+        output.print(table(&rows), value);
+        return ExitCode::SUCCESS;
+    }
+    let text = format!(
+        "{}\n\nCould not find the latest release of {}.",
+        table(&rows),
+        failed.join(", ")
+    );
+    output.print_failure(text, value);
+    ExitCode::from(CHECK_FAILED)
+}
+
+/// Prints the problems in a configuration file, then the configuration
+/// tools would use with it deployed.
+fn run_policy_validate(output: Output, matches: &ArgMatches) -> ExitCode {
+    let path = matches.get_one::<PathBuf>("path").expect("required");
+    let layer = if matches.get_flag("user") {
+        ConfigLayer::User
+    } else {
+        ConfigLayer::Policy
+    };
+    let validation = match validate_config(path, layer) {
+        Ok(validation) => validation,
+        Err(err) => {
+            output.error(format!("Could not read {}: {}", path.display(), err));
+            return ExitCode::FAILURE;
+        }
+    };
+    let problems = &validation.problems;
+    let mut text = match problems.len() {
+        0 => format!("{} is valid.", path.display()),
+        1 => format!("{} has 1 problem:", path.display()),
+        n => format!("{} has {} problems:", path.display(), n),
+    };
+    for problem in problems {
+        text.push_str(&format!("\n  - {}", problem));
+    }
+    text.push_str(&format!(
+        "\n\nEffective configuration:\n{}",
+        validation.effective
+    ));
+    let value = json!({
+        "path": path.display().to_string(),
+        "valid": problems.is_empty(),
+        "problems": problems,
+        "effective": serde_json::from_str::<Value>(&validation.effective).unwrap_or(Value::Null),
+    });
+    output.print(text, value);
+    if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// The configuration file, for error messages.
+fn config_display() -> String {
+    config_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "the configuration".to_string())
+}
+
+/// Prints how each step of checking a tool went, with the tool's own cache
+/// entry after the cache's.
+fn run_doctor(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tool = matches.get_one::<String>("tool").expect("defaulted");
+    let mut diagnostics = diagnose(tool);
+    let at = diagnostics
+        .iter()
+        .position(|diagnostic| diagnostic.step == "cache")
+        .map_or(0, |cache| cache + 1);
+    diagnostics.insert(at, tool_diagnostic(tool));
+
+    let mut text = String::new();
+    for diagnostic in &diagnostics {
+        text.push_str(&format!(
+            "{:>7}  {}: {}\n",
+            diagnostic.health.as_str(),
+            diagnostic.step,
+            diagnostic.summary
+        ));
+        for detail in &diagnostic.details {
+            text.push_str(&format!("         - {}\n", detail));
+        }
+    }
+    let value = json!({
+        "diagnostics": diagnostics
+            .iter()
+            .map(|diagnostic| json!({
+                "step": diagnostic.step,
+                "health": diagnostic.health.as_str(),
+                "summary": diagnostic.summary,
+                "details": diagnostic.details,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    output.print(text.trim_end(), value);
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.health == Health::Failed)
+    {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// What the cache says about `tool`, which decides whether its next run
+/// looks anything up.
+fn tool_diagnostic(tool: &str) -> Diagnostic {
+    let cached = cached_tools()
+        .into_iter()
+        .find(|cached| cached.tool == tool);
+    let Some(cached) = cached else {
+        return Diagnostic {
+            step: "tool",
+            health: Health::Ok,
+            summary: format!("Nothing is cached about {}: its next run checks.", tool),
+            details: Vec::new(),
+        };
+    };
+    let checked = cached
+        .last_check
+        .map(|time| format_elapsed(now().saturating_sub(time)))
+        .unwrap_or_else(|| "never".to_string());
+    let mut details = vec![format!("Last checked: {}", checked)];
+    if let Some(running) = &cached.running {
+        details.push(format!("Running: {}", running));
+    }
+    if let Some(latest) = &cached.latest {
+        details.push(format!("Latest: {}", latest));
+    }
+    if let Some(until) = cached.snoozed_until {
+        return Diagnostic {
+            step: "tool",
+            health: Health::Warning,
+            summary: format!(
+                "The notices of {} are snoozed for {} more.",
+                tool,
+                format_span(until.saturating_sub(now()))
+            ),
+            details,
+        };
+    }
+    Diagnostic {
+        step: "tool",
+        health: Health::Ok,
+        summary: format!("The cache says of {}: {}.", tool, outcome(&cached)),
+        details,
+    }
+}
+
+/// Updates a tool to its latest release the way it was installed: with its
+/// package manager, from the prebuilt asset of its GitHub release when it
+/// wasn't installed by cargo, or else with `cargo binstall` or
+/// `cargo install`.
+fn run_update(output: Output, matches: &ArgMatches) -> ExitCode {
+    let tool = matches.get_one::<String>("tool").expect("required");
+    let bin = matches.get_one::<String>("bin").unwrap_or(tool);
+    let Some(exe) = installed::executable(bin) else {
+        output.error(format!("Could not find {} on the PATH.", bin));
+        return ExitCode::FAILURE;
+    };
+    let Some(version) = installed_version(tool) else {
+        output.error(format!(
+            "Could not tell which version of {} is installed.",
+            tool
+        ));
+        return ExitCode::FAILURE;
+    };
+    let method = InstallMethod::from_path(&exe);
+    if method == InstallMethod::MozBuild {
+        output.error(format!(
+            "{} was installed by ./mach bootstrap, which updates it.",
+            tool
+        ));
+        return ExitCode::FAILURE;
+    }
+    let new_checker = || {
+        VersionChecker::new(tool, &version)
+            .executable(&exe)
+            .delegate_to_package_manager(true)
+    };
+    let installing = || {
+        let checker = new_checker();
+        let plan = checker.self_update_dry_run();
+        (checker, plan)
+    };
+
+    // Installs cargo didn't make are updated from the release asset when
+    // the crate's repository is on GitHub and has one for this target.
+    let mut downloading = None;
+    if method == InstallMethod::Unknown {
+        match CratesIo::new().github_releases(tool) {
+            Ok(Some(github)) => downloading = Some(new_checker().source(github)),
+            Ok(None) => {}
+            Err(err) => {
+                output.error(format!("Could not look {} up: {}", tool, err));
+                return ExitCode::from(CHECK_FAILED);
+            }
+        }
+    }
+    let (checker, plan) = match downloading {
+        Some(checker) => match checker.self_update_download_dry_run() {
+            Err(SelfUpdateError::NoMatchingAsset { .. }) => installing(),
+            plan => (checker, plan),
+        },
+        None => installing(),
+    };
+    let plan = match plan {
+        Ok(Some(plan)) => plan,
+        Ok(None) => {
+            output.print(
+                format!("{} {} is up to date.", tool, version),
+                json!({ "tool": tool, "from": version, "to": version, "updated": false }),
+            );
+            return ExitCode::SUCCESS;
+        }
+        Err(SelfUpdateError::LatestUnknown) => {
+            output.error(format!("Could not find the latest release of {}.", tool));
+            return ExitCode::from(CHECK_FAILED);
+        }
+        Err(err) => {
+            output.error(format!("Could not update {}: {}", tool, err));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (method, outcome) = match &plan.method {
+        UpdateMethod::Download { asset, .. } => {
+            output.note(format!("Downloading {}...", asset));
+            ("download", checker.self_update_download())
+        }
+        UpdateMethod::Installer { command } => {
+            output.note(format!("Running {}...", command));
+            ("installer", checker.self_update())
+        }
+    };
+    match outcome {
+        Ok(SelfUpdateOutcome::Updated { from, to }) => {
+            output.print(
+                format!("Updated {} from {} to {}.", tool, from, to),
+                json!({ "tool": tool, "from": from, "to": to, "updated": true, "method": method }),
+            );
+            ExitCode::SUCCESS
+        }
+        Ok(SelfUpdateOutcome::UpToDate { version }) => {
+            output.print(
+                format!("{} {} is up to date.", tool, version),
+                json!({ "tool": tool, "from": version, "to": version, "updated": false }),
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            output.error(format!("Could not update {}: {}", tool, err));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reinstalls this crate with cargo, as `self_update` would, but with the
+/// feature that builds the binary.
+fn run_self_update(output: Output) -> ExitCode {
+    let (name, version) = (env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    let latest = match check(name, version) {
+        Some(UpdateStatus::Available(update)) => update.latest,
+        Some(_) => {
+            output.print(
+                format!("moz-version-check {} is up to date.", version),
+                json!({ "from": version, "to": version, "updated": false }),
+            );
+            return ExitCode::SUCCESS;
+        }
+        None => {
+            output.error("Could not find the latest release of moz-version-check.");
+            return ExitCode::FAILURE;
+        }
+    };
+    let spec = format!("{}@{}", name, latest);
+    // Its progress goes to stderr, out of the way of `--json`.
+    let status = Process::new("cargo")
+        .args(["install", "--locked", &spec, "--features", "cli"])
+        .stdout(std::process::Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => {
+            output.print(
+                format!("Updated moz-version-check from {} to {}.", version, latest),
+                json!({ "from": version, "to": latest, "updated": true }),
+            );
+            ExitCode::SUCCESS
+        }
+        Ok(status) => {
+            output.error(format!(
+                "Self-update failed: cargo install exited with {}",
+                status
+            ));
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            output.error(format!("Self-update failed: could not run cargo: {}", err));
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use moz_cli_version_check::format_span;
+use serde_json::Value;
+use std::io::{self, Write};
+
+// Written rather than printed, so that piping into `head` doesn't panic.
+fn out(text: impl std::fmt::Display) {
+    let _ = writeln!(io::stdout(), "{}", text);
+}
+
+fn err(text: impl std::fmt::Display) {
+    let _ = writeln!(io::stderr(), "{}", text);
+}
+
+/// Prints results for people, or as JSON for scripts with `--json`: one
+/// object per result, on a line of its own.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub(crate) fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    /// Prints `text`, or `value` with `--json`.
+    pub(crate) fn print(self, text: impl AsRef<str>, value: Value) {
+        if self.json {
+            out(value);
+        } else {
+            out(text.as_ref());
+        }
+    }
+
+    /// Prints `text` to stderr, or `value` to stdout with `--json`, for
+    /// results that say what went wrong.
+    pub(crate) fn print_failure(self, text: impl AsRef<str>, value: Value) {
+        if self.json {
+            out(value);
+        } else {
+            err(text.as_ref());
+        }
+    }
+
+    /// Prints `text` to stderr whatever the mode, for what isn't a result,
+    /// such as what a long-running command is doing.
+    pub(crate) fn note(self, text: impl AsRef<str>) {
+        err(text.as_ref());
+    }
+
+    /// Prints `message` to stderr, or `{"error": message}` to stdout with
+    /// `--json`.
+    pub(crate) fn error(self, message: impl AsRef<str>) {
+        if self.json {
+            out(serde_json::json!({ "error": message.as_ref() }));
+        } else {
+            err(message.as_ref());
+        }
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub(crate) fn format_elapsed(seconds: u64) -> String {
+    format!("{} ago", format_span(seconds))
+}
+
+/// Lines of `rows` with their columns aligned.
+pub(crate) fn table<const N: usize>(rows: &[[String; N]]) -> String {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect();
+    lines.join("\n")
+}
@@ -0,0 +1,322 @@
+//! Ready-made clap subcommands, so every tool offers the same update UX.
+
+use std::process::ExitCode;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use crate::{
+    get_current_timestamp, SelfUpdateOutcome, Shell, UpdateMethod, UpdateStatus, VersionChecker,
+};
+
+/// The `self` subcommand, with `self update`, `self check` and
+/// `self shell-hook` under it.
+///
+/// ```ignore
+/// let cli = Command::new("my-tool").subcommand(moz_cli_version_check::self_command());
+/// match cli.get_matches().subcommand() {
+///     Some(("self", matches)) => return moz_cli_version_check::run_self_command(&checker, matches),
+///     // ...
+/// }
+/// ```
+pub fn self_command() -> Command {
+    let update = Command::new("update")
+        .about("Update to the latest release")
+        .arg(
+            Arg::new("rollback")
+                .long("rollback")
+                .action(ArgAction::SetTrue)
+                .help("Restore the version replaced by the last update"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("rollback")
+                .help("Show what would be done without changing anything"),
+        );
+    #[cfg(feature = "self-update-download")]
+    let update = update.arg(
+        Arg::new("prebuilt")
+            .long("prebuilt")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("rollback")
+            .help("Download the prebuilt binary instead of using cargo"),
+    );
+
+    Command::new("self")
+        .about("Manage this tool's installation")
+        .subcommand_required(true)
+        .subcommand(update)
+        .subcommand(Command::new("check").about("Check whether a newer release is available"))
+        .subcommand(
+            Command::new("shell-hook")
+                .about("Print a snippet that shows update notices when the shell starts")
+                .arg(
+                    Arg::new("shell")
+                        .required_unless_present("off")
+                        .value_parser(Shell::ALL.map(Shell::name))
+                        .help("Shell to write the snippet for"),
+                )
+                .arg(
+                    Arg::new("off")
+                        .long("off")
+                        .action(ArgAction::SetTrue)
+                        .help("Print notices in the tool's output again"),
+                ),
+        )
+}
+
+/// Exit code of [`handle_check_update`] when a newer version is available.
+pub const EXIT_UPDATE_AVAILABLE: u8 = 10;
+
+/// A `--check-update` flag to add to the tool's top-level command.
+pub fn check_update_arg() -> Arg {
+    Arg::new("check-update")
+        .long("check-update")
+        .action(ArgAction::SetTrue)
+        .help("Check for a newer version now and exit")
+}
+
+/// Implements the [`check_update_arg`] flag: if it was passed, checks for an
+/// update synchronously, prints the current and latest versions, when the
+/// cache was last refreshed and where releases come from, and returns the exit
+/// code to use. Returns `None` when the flag wasn't passed.
+///
+/// The exit code is 0 when up to date, [`EXIT_UPDATE_AVAILABLE`] when a newer
+/// version exists and 1 when the latest version could not be determined.
+pub fn handle_check_update(checker: &VersionChecker, matches: &ArgMatches) -> Option<ExitCode> {
+    if !matches.get_flag("check-update") {
+        return None;
+    }
+
+    let last_checked = crate::last_checked(&checker.tool_name);
+    let release = crate::refresh_release(
+        &*checker.source,
+        &checker.tool_name,
+        &checker.current_version,
+        &checker.version_policy,
+    );
+
+    println!(
+        "Current:      {} {}",
+        checker.tool_name, checker.current_version
+    );
+    let code = match release {
+        Some(ref release)
+            if checker
+                .version_policy
+                .is_update(&checker.current_version, &release.version) =>
+        {
+            let bump = checker
+                .version_policy
+                .bump(&checker.current_version, &release.version);
+            println!(
+                "Latest:       {} ({} update available)",
+                release.version,
+                bump.as_str()
+            );
+            ExitCode::from(EXIT_UPDATE_AVAILABLE)
+        }
+        Some(ref release)
+            if checker
+                .version_policy
+                .is_newer(&release.version, &checker.current_version) =>
+        {
+            println!(
+                "Latest:       {} (the running version is newer)",
+                release.version
+            );
+            ExitCode::SUCCESS
+        }
+        Some(ref release) => {
+            println!("Latest:       {} (up to date)", release.version);
+            ExitCode::SUCCESS
+        }
+        None => {
+            println!("Latest:       unknown (the lookup failed)");
+            ExitCode::FAILURE
+        }
+    };
+    let checked = last_checked
+        .map(|time| {
+            format!(
+                "{} ago",
+                format_span(get_current_timestamp().saturating_sub(time))
+            )
+        })
+        .unwrap_or_else(|| "never".to_string());
+    println!("Last checked: {}", checked);
+    println!("Source:       {}", checker.source.describe());
+    Some(code)
+}
+
+/// `seconds` in the largest unit that fits, such as `3 hours`. Public only
+/// for the `moz-version-check` binary, which can't reach crate items.
+#[doc(hidden)]
+pub fn format_span(seconds: u64) -> String {
+    let (count, unit) = match seconds {
+        0..=59 => (seconds, "second"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{}", count, unit, plural)
+}
+
+/// Runs the `self` subcommand built by [`self_command`], given its matches.
+pub fn run_self_command(checker: &VersionChecker, matches: &ArgMatches) -> ExitCode {
+    match matches.subcommand() {
+        Some(("update", matches)) => run_update(checker, matches),
+        Some(("check", _)) => run_check(checker),
+        Some(("shell-hook", matches)) => run_shell_hook(checker, matches),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+fn run_update(checker: &VersionChecker, matches: &ArgMatches) -> ExitCode {
+    if matches.get_flag("rollback") {
+        return match checker.rollback() {
+            Ok(()) => {
+                println!("Restored the previous version of {}.", checker.tool_name);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("Rollback failed: {}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if matches.get_flag("dry-run") {
+        return run_dry_run(checker, matches);
+    }
+
+    #[cfg(feature = "self-update-download")]
+    let result = if matches.get_flag("prebuilt") {
+        checker.self_update_download()
+    } else {
+        checker.self_update()
+    };
+    #[cfg(not(feature = "self-update-download"))]
+    let result = checker.self_update();
+
+    match result {
+        Ok(SelfUpdateOutcome::UpToDate { version }) => {
+            println!("{} {} is already up to date.", checker.tool_name, version);
+            ExitCode::SUCCESS
+        }
+        Ok(SelfUpdateOutcome::Updated { from, to }) => {
+            println!("Updated {} from {} to {}.", checker.tool_name, from, to);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Self-update failed: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_dry_run(checker: &VersionChecker, matches: &ArgMatches) -> ExitCode {
+    #[cfg(feature = "self-update-download")]
+    let plan = if matches.get_flag("prebuilt") {
+        checker.self_update_download_dry_run()
+    } else {
+        checker.self_update_dry_run()
+    };
+    #[cfg(not(feature = "self-update-download"))]
+    let plan = {
+        let _ = matches;
+        checker.self_update_dry_run()
+    };
+
+    match plan {
+        Ok(Some(plan)) => {
+            println!(
+                "Would update {} from {} to {}",
+                checker.tool_name, plan.from, plan.to
+            );
+            match plan.method {
+                UpdateMethod::Installer { command } => println!("  by running `{}`", command),
+                UpdateMethod::Download { asset, url } => {
+                    println!("  by downloading {} from {}", asset, url)
+                }
+            }
+            println!("  replacing {}", plan.destination.display());
+            ExitCode::SUCCESS
+        }
+        Ok(None) => {
+            println!(
+                "{} {} is already up to date.",
+                checker.tool_name, checker.current_version
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Self-update would fail: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Checks right away, with the exit codes of [`handle_check_update`].
+fn run_check(checker: &VersionChecker) -> ExitCode {
+    match checker.check_now_status() {
+        Some(UpdateStatus::Available(update)) => {
+            println!(
+                "{} {} is available (current: {}); run `{} self update`.",
+                update.tool, update.latest, update.current, update.tool
+            );
+            ExitCode::from(EXIT_UPDATE_AVAILABLE)
+        }
+        Some(UpdateStatus::AheadOfRegistry { current, latest }) => {
+            println!(
+                "{} {} is newer than the latest release, {}.",
+                checker.tool_name, current, latest
+            );
+            ExitCode::SUCCESS
+        }
+        Some(UpdateStatus::UpToDate) => {
+            println!(
+                "{} {} is up to date.",
+                checker.tool_name, checker.current_version
+            );
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!(
+                "Could not determine the latest version of {}: the lookup failed.",
+                checker.tool_name
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the snippet for the requested shell, and has notices go to it from
+/// now on; with `--off`, back to the tool's output.
+fn run_shell_hook(checker: &VersionChecker, matches: &ArgMatches) -> ExitCode {
+    let enabled = !matches.get_flag("off");
+    if let Err(err) = checker.set_shell_notices(enabled) {
+        eprintln!("Could not change where notices are shown: {}", err);
+        return ExitCode::FAILURE;
+    }
+    if !enabled {
+        eprintln!(
+            "{} will print update notices itself again.",
+            checker.tool_name
+        );
+        return ExitCode::SUCCESS;
+    }
+    let shell = matches
+        .get_one::<String>("shell")
+        .and_then(|name| Shell::from_name(name))
+        .unwrap_or(Shell::Bash);
+    print!("{}", crate::shell_hook(shell));
+    eprintln!(
+        "Add the lines above to your {} startup file; {} will leave its update notices to it.",
+        shell.name(),
+        checker.tool_name
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,311 @@
+//! The user's configuration, `~/.mozbuild/version-check-config.json`, which
+//! every tool using the crate honors:
+//!
+//! ```json
+//! {
+//!   "tools": {
+//!     "searchfox-cli": { "pin": "^1" }
+//!   }
+//! }
+//! ```
+//!
+//! Administrators can deploy a policy in the same format for every user of
+//! a machine, which wins over each user's configuration where both set
+//! something. Both are read as plain JSON whatever the `tiny` feature; what
+//! isn't valid is ignored, and the user's file is rewritten with any key it
+//! doesn't know kept as it was.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use semver::VersionReq;
+use serde_json::{Map, Value};
+
+/// Set to the path of a configuration file to read instead of the user's.
+const CONFIG_ENV: &str = "MOZTOOLS_UPDATE_CHECK_CONFIG";
+
+/// Set to the path of a policy file to read instead of the machine's.
+const POLICY_ENV: &str = "MOZTOOLS_UPDATE_CHECK_POLICY";
+
+/// Where the configuration is read from: `MOZTOOLS_UPDATE_CHECK_CONFIG`, or
+/// else `~/.mozbuild/version-check-config.json`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::home_dir().map(|home| home.join(".mozbuild").join("version-check-config.json"))
+}
+
+/// Where the machine's policy is read from: `MOZTOOLS_UPDATE_CHECK_POLICY`,
+/// or else `/etc/mozilla/version-check-policy.json`, its equivalent under
+/// `/Library/Application Support` on macOS, or under `%ProgramData%` on
+/// Windows.
+pub fn policy_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(POLICY_ENV).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let dir = if cfg!(windows) {
+        PathBuf::from(std::env::var_os("ProgramData")?).join("Mozilla")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/Mozilla")
+    } else if cfg!(unix) {
+        PathBuf::from("/etc/mozilla")
+    } else {
+        return None;
+    };
+    Some(dir.join("version-check-policy.json"))
+}
+
+/// Which of the two files a configuration is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigLayer {
+    /// The user's, at [`config_path`].
+    User,
+    /// The machine's, at [`policy_path`], which wins over the user's.
+    Policy,
+}
+
+/// The file at `path` as found, or an empty object if there is none or it
+/// isn't a JSON object.
+fn read(path: Option<PathBuf>) -> Map<String, Value> {
+    path.and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| match serde_json::from_str(&content) {
+            Ok(Value::Object(config)) => Some(config),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// The configuration in effect: the valid settings of the user's, with the
+/// policy's on top.
+fn load() -> Map<String, Value> {
+    let mut config = valid(read(config_path()), &mut Vec::new());
+    merge(&mut config, valid(read(policy_path()), &mut Vec::new()));
+    config
+}
+
+/// Sets everything `over` sets in `base`, merging objects key by key.
+fn merge(base: &mut Map<String, Value>, over: Map<String, Value>) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Object(base)), Value::Object(value)) => merge(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The settings of `config` the crate knows and can use, adding what is
+/// wrong with the others to `problems`.
+fn valid(config: Map<String, Value>, problems: &mut Vec<String>) -> Map<String, Value> {
+    let mut valid = Map::new();
+    for (key, value) in config {
+        match (key.as_str(), value) {
+            ("tools", Value::Object(tools)) => {
+                let tools = tools
+                    .into_iter()
+                    .filter_map(|(tool, settings)| {
+                        let settings = valid_tool(&tool, settings, problems)?;
+                        (!settings.is_empty()).then_some((tool, Value::Object(settings)))
+                    })
+                    .collect();
+                valid.insert(key, Value::Object(tools));
+            }
+            ("tools", _) => problems.push("tools: expected an object of tools".to_string()),
+            _ => problems.push(format!("{}: unknown key", key)),
+        }
+    }
+    valid
+}
+
+fn valid_tool(
+    tool: &str,
+    settings: Value,
+    problems: &mut Vec<String>,
+) -> Option<Map<String, Value>> {
+    let Value::Object(settings) = settings else {
+        problems.push(format!("tools.{}: expected an object of settings", tool));
+        return None;
+    };
+    let mut valid = Map::new();
+    for (key, value) in settings {
+        match (key.as_str(), &value) {
+            ("pin", Value::String(pin)) => match VersionReq::parse(pin) {
+                Ok(_) => {
+                    valid.insert(key, value);
+                }
+                Err(err) => problems.push(format!(
+                    "tools.{}.pin: invalid version requirement {:?}: {}",
+                    tool, pin, err
+                )),
+            },
+            ("pin", _) => problems.push(format!(
+                "tools.{}.pin: expected a version requirement such as \"^1\"",
+                tool
+            )),
+            _ => problems.push(format!("tools.{}.{}: unknown key", tool, key)),
+        }
+    }
+    Some(valid)
+}
+
+/// What [`validate_config`] found in a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigValidation {
+    /// What is wrong, one problem each, starting with where it is, as in
+    /// `tools.my-tool.pin: invalid version requirement`.
+    pub problems: Vec<String>,
+    /// The configuration tools would use with the file deployed as `layer`,
+    /// as pretty-printed JSON: its valid settings merged with the other
+    /// file found on this machine.
+    pub effective: String,
+}
+
+/// Checks the configuration at `path` before it is deployed as `layer`,
+/// such as a policy for a fleet of machines, telling which keys are unknown
+/// and which values invalid; tools ignore those. Errors only when the file
+/// can't be read.
+pub fn validate_config(path: &Path, layer: ConfigLayer) -> io::Result<ConfigValidation> {
+    let content = fs::read_to_string(path)?;
+    let mut problems = Vec::new();
+    let config = match serde_json::from_str(&content) {
+        Ok(Value::Object(config)) => valid(config, &mut problems),
+        Ok(_) => {
+            problems.push("expected a JSON object".to_string());
+            Map::new()
+        }
+        Err(err) => {
+            problems.push(format!("not valid JSON: {}", err));
+            Map::new()
+        }
+    };
+    let effective = match layer {
+        ConfigLayer::User => {
+            let mut effective = config;
+            merge(&mut effective, valid(read(policy_path()), &mut Vec::new()));
+            effective
+        }
+        ConfigLayer::Policy => {
+            let mut effective = valid(read(config_path()), &mut Vec::new());
+            merge(&mut effective, config);
+            effective
+        }
+    };
+    Ok(ConfigValidation {
+        problems,
+        effective: serde_json::to_string_pretty(&Value::Object(effective))?,
+    })
+}
+
+fn save(config: Map<String, Value>) -> io::Result<()> {
+    let path = config_path().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "no home directory to keep it in")
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(&Value::Object(config))?;
+    fs::write(path, content + "\n")
+}
+
+/// The user's configuration, to change it: unlike [`read`], a file that exists
+/// but can't be read or isn't a JSON object is an error rather than empty, so
+/// that saving doesn't overwrite what the user wrote.
+fn read_to_update() -> io::Result<Map<String, Value>> {
+    let Some(path) = config_path() else {
+        return Ok(Map::new());
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(err) => return Err(err),
+    };
+    match serde_json::from_str(&content) {
+        Ok(Value::Object(config)) => Ok(config),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a JSON object",
+        )),
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+    }
+}
+
+/// The settings of `tool_name` under `"tools"`.
+fn tool_settings<'a>(config: &'a Map<String, Value>, tool_name: &str) -> Option<&'a Value> {
+    config.get("tools")?.get(tool_name)
+}
+
+/// The versions `tool_name` is pinned to, if the user pinned it. A pin that
+/// doesn't parse is ignored here; `moz-version-check pin` never writes one.
+pub(crate) fn pin(tool_name: &str) -> Option<VersionReq> {
+    let config = load();
+    let pin = tool_settings(&config, tool_name)?.get("pin")?.as_str()?;
+    VersionReq::parse(pin).ok()
+}
+
+/// Every tool the user pinned, with the versions it is pinned to, sorted by
+/// name.
+pub fn pinned_tools() -> Vec<(String, VersionReq)> {
+    let config = load();
+    let Some(Value::Object(tools)) = config.get("tools") else {
+        return Vec::new();
+    };
+    let mut pinned: Vec<(String, VersionReq)> = tools
+        .iter()
+        .filter_map(|(tool, settings)| {
+            let pin = VersionReq::parse(settings.get("pin")?.as_str()?).ok()?;
+            Some((tool.clone(), pin))
+        })
+        .collect();
+    pinned.sort_by(|a, b| a.0.cmp(&b.0));
+    pinned
+}
+
+/// Pins `tool_name` to the versions `requirement` matches, in the user's
+/// configuration: every tool using the crate then only offers updates to
+/// those, e.g. `^1` to stay on 1.x, unless the policy pins it differently.
+///
+/// Fails with [`io::ErrorKind::InvalidData`], leaving the file alone, if the
+/// configuration exists but isn't valid JSON.
+pub fn pin_tool(tool_name: &str, requirement: &VersionReq) -> io::Result<()> {
+    let mut config = read_to_update()?;
+    let tools = config
+        .entry("tools")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !tools.is_object() {
+        *tools = Value::Object(Map::new());
+    }
+    let settings = tools
+        .as_object_mut()
+        .expect("made an object")
+        .entry(tool_name)
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !settings.is_object() {
+        *settings = Value::Object(Map::new());
+    }
+    settings["pin"] = Value::String(requirement.to_string());
+    save(config)
+}
+
+/// Removes the pin of `tool_name` from the user's configuration. Returns
+/// whether it had one; a pin in the policy stays in effect. Fails like
+/// [`pin_tool`] if the configuration isn't valid JSON.
+pub fn unpin_tool(tool_name: &str) -> io::Result<bool> {
+    let mut config = read_to_update()?;
+    let Some(Value::Object(tools)) = config.get_mut("tools") else {
+        return Ok(false);
+    };
+    let Some(Value::Object(settings)) = tools.get_mut(tool_name) else {
+        return Ok(false);
+    };
+    if settings.remove("pin").is_none() {
+        return Ok(false);
+    }
+    if settings.is_empty() {
+        tools.remove(tool_name);
+    }
+    save(config)?;
+    Ok(true)
+}
@@ -0,0 +1,71 @@
+use crate::Output;
+
+/// Makes sure escape sequences written to `output` are interpreted rather
+/// than printed raw, returning whether that is the case.
+///
+/// On Windows this turns on virtual terminal processing for the console,
+/// which older cmd.exe/ConHost setups leave off. Everywhere else escape
+/// sequences are assumed to work.
+#[cfg(windows)]
+pub(crate) fn enable_ansi(output: Output) -> bool {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+        STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    let handle = match output {
+        Output::Stderr => STD_ERROR_HANDLE,
+        Output::Stdout => STD_OUTPUT_HANDLE,
+        Output::Suppressed => return false,
+    };
+
+    // SAFETY: these calls only read and update the mode of a standard handle
+    // owned by the process; invalid handles make them fail, not misbehave.
+    unsafe {
+        let handle = GetStdHandle(handle);
+        let mut mode = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            // Not a console (redirected to a file or pipe): nothing to enable.
+            return true;
+        }
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn enable_ansi(_output: Output) -> bool {
+    true
+}
+
+/// Removes CSI (`ESC [ ... final`) and OSC (`ESC ] ... ST`) escape sequences.
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
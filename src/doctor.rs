@@ -0,0 +1,429 @@
+//! Trying each step an update check depends on, one at a time, to explain
+//! why a notice never shows up or shows up every time.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{checks_disabled, config, elapsed, http, json, start_timer, store, VersionCache};
+
+const REGISTRY_HOST: &str = "crates.io";
+
+/// How one step of [`diagnose`] went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Ok,
+    /// Works, but not as users may expect, e.g. checks are disabled.
+    Warning,
+    Failed,
+    /// Not tried, as it doesn't apply or an earlier step failed.
+    Skipped,
+}
+
+impl Health {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Health::Ok => "ok",
+            Health::Warning => "warning",
+            Health::Failed => "failed",
+            Health::Skipped => "skipped",
+        }
+    }
+}
+
+/// One step an update check depends on, as [`diagnose`] found it.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// What was tried, such as `"cache"` or `"registry"`.
+    pub step: &'static str,
+    pub health: Health,
+    /// What was found, in a sentence.
+    pub summary: String,
+    /// The facts behind it, one per line.
+    pub details: Vec<String>,
+}
+
+impl Diagnostic {
+    fn new(step: &'static str, health: Health, summary: impl Into<String>) -> Self {
+        Self {
+            step,
+            health,
+            summary: summary.into(),
+            details: Vec::new(),
+        }
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.details.push(detail.into());
+        self
+    }
+
+    fn failed(mut self, summary: &str, err: impl std::fmt::Display) -> Self {
+        self.health = Health::Failed;
+        self.summary = summary.to_string();
+        self.detail(format!("Error: {}", err))
+    }
+}
+
+/// Tries each step of checking `tool_name` for updates, in the order a check
+/// takes them: the configuration, the cache, the HTTP backend, the proxy,
+/// resolving and connecting to crates.io, and fetching the crate from it
+/// over TLS. Nothing is cached and no notice is printed; the last steps make
+/// real requests, unlike a check answered from the cache.
+pub fn diagnose(tool_name: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![configuration(), cache(), backend()];
+    let (proxy, proxied) = proxy();
+    diagnostics.push(proxy);
+    let connected = network(&mut diagnostics, proxied);
+    diagnostics.push(registry(tool_name, connected));
+    diagnostics
+}
+
+fn configuration() -> Diagnostic {
+    let mut diagnostic = if checks_disabled() {
+        Diagnostic::new(
+            "configuration",
+            Health::Warning,
+            "MOZTOOLS_UPDATE_CHECK=0 disables update checks, so no notice is shown.",
+        )
+    } else if let Ok(latest) = std::env::var("MOZTOOLS_FAKE_LATEST") {
+        Diagnostic::new(
+            "configuration",
+            Health::Warning,
+            format!(
+                "MOZTOOLS_FAKE_LATEST makes {} the latest release of every tool.",
+                latest
+            ),
+        )
+    } else {
+        Diagnostic::new("configuration", Health::Ok, "Update checks are enabled.")
+    };
+    let mut variables: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| name.starts_with("MOZTOOLS_"))
+        .collect();
+    variables.sort();
+    for (name, value) in variables {
+        diagnostic = diagnostic.detail(format!("{}={}", name, value));
+    }
+    if let Some(path) = config::config_path().filter(|path| path.is_file()) {
+        diagnostic = diagnostic.detail(format!("Config file: {}", path.display()));
+    }
+    if let Some(path) = config::policy_path().filter(|path| path.is_file()) {
+        diagnostic = diagnostic.detail(format!("Policy file: {}", path.display()));
+    }
+    for (tool, pin) in config::pinned_tools() {
+        diagnostic = diagnostic.detail(format!("{} is pinned to {}", tool, pin));
+    }
+    diagnostic
+}
+
+fn cache() -> Diagnostic {
+    if store::is_custom() {
+        return Diagnostic::new(
+            "cache",
+            Health::Ok,
+            "The cache is kept in the store given to set_cache_store.",
+        );
+    }
+    let Some(path) = store::cache_path() else {
+        return Diagnostic::new(
+            "cache",
+            Health::Failed,
+            "There is no home directory to keep the cache in, so every run checks again.",
+        );
+    };
+    let mut diagnostic = Diagnostic::new("cache", Health::Ok, "The cache can be read and written.")
+        .detail(format!("File: {}", path.display()));
+    if let Some(dir) = store::tools_dir().filter(|dir| dir.is_dir()) {
+        diagnostic = diagnostic.detail(format!("Per-tool files: {}", dir.display()));
+    }
+    match fs::read_to_string(&path) {
+        Ok(content) => match json::from_str::<VersionCache>(&content) {
+            Ok(cache) => {
+                diagnostic = diagnostic.detail(format!("Tools cached: {}", cache.tools.len()))
+            }
+            Err(err) => {
+                diagnostic.health = Health::Warning;
+                diagnostic.summary =
+                    "The cache file is not valid, so it is started over at the next check."
+                        .to_string();
+                diagnostic = diagnostic.detail(format!("Error: {}", err));
+            }
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            diagnostic = diagnostic.detail("The file doesn't exist yet: no tool was checked.")
+        }
+        Err(err) => {
+            return diagnostic.failed("The cache can't be read, so every run checks again.", err)
+        }
+    }
+    match writable(&path) {
+        Ok(()) => diagnostic,
+        Err(err) => diagnostic.failed(
+            "The cache can't be written, so every run checks and notifies again.",
+            err,
+        ),
+    }
+}
+
+/// Whether `path` could be written, without changing it: an existing file is
+/// opened for appending, and otherwise a file is made and deleted in the
+/// closest directory there is.
+fn writable(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return fs::OpenOptions::new().append(true).open(path).map(drop);
+    }
+    let Some(dir) = path.ancestors().skip(1).find(|dir| dir.is_dir()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "none of its directories exists",
+        ));
+    };
+    let probe = dir.join(format!(".version-check-probe-{}", std::process::id()));
+    fs::File::create(&probe)?;
+    fs::remove_file(probe)
+}
+
+fn backend() -> Diagnostic {
+    if http::fetch_registered() {
+        return Diagnostic::new(
+            "http",
+            Health::Ok,
+            "Requests are sent with the function given to set_fetch.",
+        );
+    }
+    match http::backend_name() {
+        Some(name) => Diagnostic::new(
+            "http",
+            Health::Ok,
+            format!("Requests are sent with {}.", name),
+        ),
+        None => Diagnostic::new(
+            "http",
+            Health::Failed,
+            "No HTTP backend is built in, nor set_fetch called, so checks can't fetch anything.",
+        ),
+    }
+}
+
+/// The proxy variables the backends honor, with any password hidden, and
+/// whether requests to the registry go through a proxy.
+fn proxy() -> (Diagnostic, bool) {
+    let names = [
+        "HTTPS_PROXY",
+        "https_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+        "NO_PROXY",
+        "no_proxy",
+    ];
+    let set: Vec<(&str, String)> = names
+        .iter()
+        .filter_map(|name| Some((*name, std::env::var(name).ok().filter(|v| !v.is_empty())?)))
+        .collect();
+    let proxy = set
+        .iter()
+        .find(|(name, _)| !name.eq_ignore_ascii_case("no_proxy"))
+        .map(|(_, value)| hide_password(value));
+    let bypassed = set
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("no_proxy"))
+        .any(|(_, hosts)| {
+            hosts
+                .split(',')
+                .map(|host| host.trim().trim_start_matches('.'))
+                .filter(|host| !host.is_empty())
+                .any(bypasses_registry)
+        });
+    let mut diagnostic = match &proxy {
+        Some(proxy) if !bypassed => Diagnostic::new(
+            "proxy",
+            Health::Ok,
+            format!("Requests go through the proxy at {}.", proxy),
+        ),
+        _ => Diagnostic::new(
+            "proxy",
+            Health::Ok,
+            format!("Requests connect to {} directly.", REGISTRY_HOST),
+        ),
+    };
+    for (name, value) in &set {
+        diagnostic = diagnostic.detail(format!("{}={}", name, hide_password(value)));
+    }
+    (diagnostic, proxy.is_some() && !bypassed)
+}
+
+/// Whether the `NO_PROXY` entry `host`, without its leading dot, covers the
+/// registry: `*`, the host itself, or a parent domain, matched on a label
+/// boundary so that `tes.io` doesn't count.
+fn bypasses_registry(host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    host == "*" || host == REGISTRY_HOST || REGISTRY_HOST.ends_with(&format!(".{}", host))
+}
+
+/// `url` with the password, if any, replaced by `***`.
+fn hide_password(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let Some((credentials, host)) = rest.rsplit_once('@') else {
+        return url.to_string();
+    };
+    let user = credentials.split(':').next().unwrap_or_default();
+    let scheme = if scheme.is_empty() {
+        String::new()
+    } else {
+        format!("{}://", scheme)
+    };
+    format!("{}{}:***@{}", scheme, user, host)
+}
+
+/// Resolves and connects to the registry, unless a proxy does both, and
+/// returns whether the connection worked.
+#[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+fn network(diagnostics: &mut Vec<Diagnostic>, proxied: bool) -> bool {
+    use std::net::{SocketAddr, TcpStream};
+
+    if proxied || http::fetch_registered() {
+        let why = if proxied {
+            "The proxy resolves and connects to the registry."
+        } else {
+            "The function given to set_fetch resolves and connects to the registry."
+        };
+        diagnostics.push(Diagnostic::new("dns", Health::Skipped, why));
+        diagnostics.push(Diagnostic::new("connection", Health::Skipped, why));
+        return false;
+    }
+
+    let started = start_timer();
+    let addrs = match http::resolve(REGISTRY_HOST, 443) {
+        Ok(addrs) if !addrs.is_empty() => addrs,
+        result => {
+            let err = result
+                .err()
+                .map_or("no address".to_string(), |e| e.to_string());
+            diagnostics.push(
+                Diagnostic::new(
+                    "dns",
+                    Health::Failed,
+                    format!("{} could not be resolved.", REGISTRY_HOST),
+                )
+                .detail(format!("Error: {}", err)),
+            );
+            diagnostics.push(Diagnostic::new(
+                "connection",
+                Health::Skipped,
+                "There is no address to connect to.",
+            ));
+            return false;
+        }
+    };
+    let listed: Vec<String> = addrs.iter().map(|addr| addr.to_string()).collect();
+    diagnostics.push(
+        Diagnostic::new(
+            "dns",
+            Health::Ok,
+            format!(
+                "{} resolved in {}.",
+                REGISTRY_HOST,
+                millis(elapsed(started))
+            ),
+        )
+        .detail(format!("Addresses: {}", listed.join(", "))),
+    );
+
+    let timeout = Duration::from_secs(crate::CHECK_TIMEOUT_SECONDS);
+    let started = start_timer();
+    let mut errors = Vec::new();
+    for addr in &addrs {
+        match TcpStream::connect_timeout(&SocketAddr::new(*addr, 443), timeout) {
+            Ok(_) => {
+                diagnostics.push(Diagnostic::new(
+                    "connection",
+                    Health::Ok,
+                    format!(
+                        "Connected to {} port 443 in {}.",
+                        addr,
+                        millis(elapsed(started))
+                    ),
+                ));
+                return true;
+            }
+            Err(err) => errors.push(format!("{}: {}", addr, err)),
+        }
+    }
+    let mut diagnostic = Diagnostic::new(
+        "connection",
+        Health::Failed,
+        format!("Could not connect to {} port 443.", REGISTRY_HOST),
+    );
+    diagnostic.details = errors;
+    diagnostics.push(diagnostic);
+    false
+}
+
+#[cfg(not(any(feature = "reqwest", feature = "ureq", feature = "curl")))]
+fn network(diagnostics: &mut Vec<Diagnostic>, _proxied: bool) -> bool {
+    let why = "Whatever sends requests resolves and connects to the registry.";
+    diagnostics.push(Diagnostic::new("dns", Health::Skipped, why));
+    diagnostics.push(Diagnostic::new("connection", Health::Skipped, why));
+    false
+}
+
+/// Fetches `tool_name` from the registry, which takes the TLS handshake and
+/// an HTTP request, as a check would.
+fn registry(tool_name: &str, connected: bool) -> Diagnostic {
+    let url = format!("https://{}/api/v1/crates/{}", REGISTRY_HOST, tool_name);
+    let client = http::Client::new(
+        format!("{}/version-check", tool_name),
+        Some(Duration::from_secs(crate::CHECK_TIMEOUT_SECONDS)),
+    );
+    let started = start_timer();
+    let diagnostic = match client.get(&url).send() {
+        Ok(response) if response.is_success() => Diagnostic::new(
+            "registry",
+            Health::Ok,
+            format!(
+                "{} answered about {} in {}.",
+                REGISTRY_HOST,
+                tool_name,
+                millis(elapsed(started))
+            ),
+        ),
+        Ok(response) if response.status() == 404 => Diagnostic::new(
+            "registry",
+            Health::Warning,
+            format!(
+                "{} has no crate named {}, so checks find no release.",
+                REGISTRY_HOST, tool_name
+            ),
+        ),
+        Ok(response) => Diagnostic::new(
+            "registry",
+            Health::Failed,
+            format!(
+                "{} answered with HTTP status {}.",
+                REGISTRY_HOST,
+                response.status()
+            ),
+        ),
+        Err(err) => {
+            let diagnostic = Diagnostic::new(
+                "registry",
+                Health::Failed,
+                format!("Could not fetch {} from {}.", tool_name, REGISTRY_HOST),
+            )
+            .detail(format!("Error: {}", err));
+            if connected {
+                diagnostic
+                    .detail("Connecting worked, so the TLS handshake or the request itself failed.")
+            } else {
+                diagnostic
+            }
+        }
+    };
+    diagnostic.detail(format!("URL: {}", url))
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{} ms", duration.as_millis())
+}
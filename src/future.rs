@@ -0,0 +1,59 @@
+//! Awaiting the background check from async code, on any executor.
+
+use crate::{UpdateStatus, VersionChecker};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Where the background check finds the task to wake once it is done.
+#[derive(Clone, Default)]
+pub(crate) struct WakerSlot(Arc<Mutex<Option<Waker>>>);
+
+impl WakerSlot {
+    pub(crate) fn register(&self, waker: &Waker) {
+        if let Ok(mut slot) = self.0.lock() {
+            *slot = Some(waker.clone());
+        }
+    }
+
+    fn wake(&self) {
+        let waker = self.0.lock().ok().and_then(|mut slot| slot.take());
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Wakes the waiting task when dropped, at the end of the background check
+/// whether it sent its outcome or panicked.
+pub(crate) struct WakeOnDrop(pub(crate) WakerSlot);
+
+impl Drop for WakeOnDrop {
+    fn drop(&mut self) {
+        self.0.wake();
+    }
+}
+
+/// The outcome of the background check, from
+/// [`VersionChecker::status_async`].
+///
+/// Polling it never blocks: the background thread wakes the task when it
+/// is done, so no thread of the executor, or spawned for blocking work, sits
+/// waiting. It has no timeout of its own; use the executor's to give up.
+#[must_use = "futures do nothing unless awaited"]
+pub struct StatusFuture<'a> {
+    pub(crate) checker: &'a VersionChecker,
+}
+
+impl Future for StatusFuture<'_> {
+    type Output = Option<UpdateStatus>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let checker = self.checker;
+        if checker.poll_check(cx.waker()).is_pending() {
+            return Poll::Pending;
+        }
+        Poll::Ready(checker.wait_for_status(std::time::Duration::ZERO))
+    }
+}
@@ -0,0 +1,39 @@
+use std::process::{Command, Stdio};
+
+/// Set to the tool's name in the environment of the helper process, which
+/// refreshes the cache from `check_async` and exits.
+pub(crate) const HELPER_ENV: &str = "MOZTOOLS_UPDATE_CHECK_HELPER";
+
+/// Whether this process is the helper started for `tool`.
+pub(crate) fn is_helper(tool: &str) -> bool {
+    std::env::var(HELPER_ENV).is_ok_and(|helper| helper == tool)
+}
+
+/// Starts the running executable again, with no arguments and no terminal,
+/// as the helper for `tool`. It isn't waited for, and outlives this process.
+pub(crate) fn spawn(tool: &str) -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    let mut command = Command::new(exe);
+    command
+        .env(HELPER_ENV, tool)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Out of the terminal's process group, so that Ctrl-C in the shell
+    // doesn't reach it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
+    }
+    command.spawn().is_ok()
+}
@@ -0,0 +1,104 @@
+use curl::easy::{Easy, List};
+
+use super::dns;
+use super::{Error, Method, Request, Response, CONNECT_TIMEOUT};
+
+impl From<curl::Error> for Error {
+    fn from(err: curl::Error) -> Self {
+        Error::new(err.to_string())
+    }
+}
+
+/// Sends `request` with the system's libcurl. The body is read in full
+/// before returning, as libcurl hands it over through a callback.
+pub(super) fn send(request: Request) -> Result<Response, Error> {
+    let mut easy = Easy::new();
+    easy.url(&request.url)?;
+    match request.method {
+        Method::Get => easy.get(true)?,
+        Method::Head => easy.nobody(true)?,
+    }
+    easy.follow_location(true)?;
+    easy.connect_timeout(CONNECT_TIMEOUT)?;
+    if let Some(timeout) = request.timeout {
+        easy.timeout(timeout)?;
+    }
+    let mut list = List::new();
+    for (name, value) in &request.headers {
+        list.append(&format!("{}: {}", name, value))?;
+    }
+    easy.http_headers(list)?;
+    if let Some(resolve) = resolve(&request.url)? {
+        easy.resolve(resolve)?;
+    }
+
+    let mut headers = Vec::new();
+    let mut body = Vec::new();
+    {
+        let mut transfer = easy.transfer();
+        transfer.header_function(|line| {
+            let line = String::from_utf8_lossy(line);
+            // Each response followed through a redirect starts over.
+            if line.starts_with("HTTP/") {
+                headers.clear();
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+            true
+        })?;
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+
+    Ok(Response {
+        status: u16::try_from(easy.response_code()?).unwrap_or(0),
+        #[cfg(feature = "self-update-download")]
+        headers,
+        body: Box::new(std::io::Cursor::new(body)),
+    })
+}
+
+/// The addresses to connect to for the host of `url`, in libcurl's
+/// `host:port:addresses` form, when [`set_dns`](crate::set_dns) has a say.
+/// Hosts reached through redirects are left to libcurl.
+fn resolve(url: &str) -> Result<Option<List>, Error> {
+    let Some(dns) = dns::config() else {
+        return Ok(None);
+    };
+    let Some((host, port)) = host_and_port(url) else {
+        return Ok(None);
+    };
+    let addrs = dns
+        .resolve(host, port)
+        .map_err(|err| Error::new(err.to_string()))?;
+    let addrs: Vec<String> = addrs
+        .iter()
+        .map(|ip| match ip {
+            std::net::IpAddr::V4(ip) => ip.to_string(),
+            std::net::IpAddr::V6(ip) => format!("[{}]", ip),
+        })
+        .collect();
+    let mut list = List::new();
+    list.append(&format!("{}:{}:{}", host, port, addrs.join(",")))?;
+    Ok(Some(list))
+}
+
+/// The host name and port of `url`, unless its host is an address already.
+fn host_and_port(url: &str) -> Option<(&str, u16)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if authority.starts_with('[') {
+        return None;
+    }
+    let (host, port) = match authority.split_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (authority, if scheme == "http" { 80 } else { 443 }),
+    };
+    (host.parse::<std::net::IpAddr>().is_err() && !host.is_empty()).then_some((host, port))
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+type ResolveFn = dyn Fn(&str) -> Option<Vec<IpAddr>> + Send + Sync;
+
+static DNS: RwLock<Option<Arc<DnsConfig>>> = RwLock::new(None);
+
+/// Set to a number of milliseconds to bound how long resolving a host name
+/// may take, over any [`DnsConfig::timeout`].
+#[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+const DNS_TIMEOUT_ENV: &str = "MOZTOOLS_UPDATE_CHECK_DNS_TIMEOUT_MS";
+
+/// How the host names of release sources are resolved, for networks where
+/// the system resolver is slow or answers wrongly. Given to [`set_dns`].
+///
+/// ```
+/// use moz_cli_version_check::{set_dns, DnsConfig};
+/// use std::time::Duration;
+///
+/// set_dns(
+///     DnsConfig::new()
+///         .timeout(Duration::from_millis(500))
+///         .host("crates.io", ["127.0.0.1".parse().unwrap()]),
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct DnsConfig {
+    timeout: Option<Duration>,
+    hosts: HashMap<String, Vec<IpAddr>>,
+    resolver: Option<Arc<ResolveFn>>,
+}
+
+impl std::fmt::Debug for DnsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DnsConfig")
+            .field("timeout", &self.timeout)
+            .field("hosts", &self.hosts)
+            .field("resolver", &self.resolver.is_some())
+            .finish()
+    }
+}
+
+impl DnsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives up on a lookup by the system resolver after `timeout`, failing
+    /// the request, instead of after however long the system takes.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connects to `addrs` for `host` without asking any resolver, like an
+    /// entry in `/etc/hosts`.
+    pub fn host(mut self, host: &str, addrs: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.hosts
+            .insert(host.to_ascii_lowercase(), addrs.into_iter().collect());
+        self
+    }
+
+    /// Asks `resolve` for the addresses of hosts not given to
+    /// [`host`](Self::host), falling back to the system resolver when it
+    /// returns `None`.
+    pub fn resolver(
+        mut self,
+        resolve: impl Fn(&str) -> Option<Vec<IpAddr>> + Send + Sync + 'static,
+    ) -> Self {
+        self.resolver = Some(Arc::new(resolve));
+        self
+    }
+
+    /// The addresses given for `host`, or `None` to ask the system.
+    #[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+    pub(crate) fn lookup(&self, host: &str) -> Option<Vec<IpAddr>> {
+        if let Some(addrs) = self.hosts.get(&host.to_ascii_lowercase()) {
+            return Some(addrs.clone());
+        }
+        self.resolver.as_ref()?(host).filter(|addrs| !addrs.is_empty())
+    }
+
+    /// How long the system resolver may take.
+    #[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+    pub(crate) fn lookup_timeout(&self) -> Option<Duration> {
+        std::env::var(DNS_TIMEOUT_ENV)
+            .ok()
+            .and_then(|millis| millis.parse().ok())
+            .map(Duration::from_millis)
+            .or(self.timeout)
+    }
+
+    /// Resolves `host` as the backends without a resolver hook of their own
+    /// need it, and as `doctor` tries it: from the given addresses, or else
+    /// from the system resolver on a thread of its own, abandoned after the
+    /// timeout.
+    #[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+    pub(crate) fn resolve(&self, host: &str, port: u16) -> std::io::Result<Vec<IpAddr>> {
+        use std::io;
+        use std::net::ToSocketAddrs;
+
+        if let Some(addrs) = self.lookup(host) {
+            return Ok(addrs);
+        }
+        let target = (host.to_string(), port);
+        let system = move || -> io::Result<Vec<IpAddr>> {
+            Ok(target.to_socket_addrs()?.map(|addr| addr.ip()).collect())
+        };
+        let Some(timeout) = self.lookup_timeout() else {
+            return system();
+        };
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        std::thread::spawn(move || tx.send(system()));
+        rx.recv_timeout(timeout).unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("resolving {} took longer than {:?}", host, timeout),
+            ))
+        })
+    }
+}
+
+/// Resolves host names as `config` says from now on, for the whole process.
+/// Backends that keep a client read it when building it, so call this before
+/// the first check; it replaces any configuration set before.
+///
+/// With no configuration, or a function given to [`set_fetch`](crate::set_fetch)
+/// in charge of requests, host names are resolved as the HTTP backend does
+/// by default. The `MOZTOOLS_UPDATE_CHECK_DNS_TIMEOUT_MS` environment
+/// variable sets the timeout in any case.
+pub fn set_dns(config: DnsConfig) {
+    *DNS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(config));
+}
+
+/// The configuration to resolve with, or `None` to leave it to the backend.
+#[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+pub(crate) fn config() -> Option<Arc<DnsConfig>> {
+    let config = DNS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if config.is_none() && std::env::var_os(DNS_TIMEOUT_ENV).is_some() {
+        return Some(Arc::default());
+    }
+    config
+}
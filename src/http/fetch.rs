@@ -0,0 +1,102 @@
+use std::io::Read;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::{Error, Method, Request, Response};
+
+type FetchFn = dyn Fn(&FetchRequest) -> Result<FetchResponse, String> + Send + Sync;
+
+static FETCH: RwLock<Option<Arc<FetchFn>>> = RwLock::new(None);
+
+/// A request the crate wants sent, handed to the function given to
+/// [`set_fetch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchRequest {
+    /// `"GET"` or `"HEAD"`.
+    pub method: &'static str,
+    pub url: String,
+    /// Headers to send, `User-Agent` included.
+    pub headers: Vec<(String, String)>,
+    /// How long the whole request may take, when the crate cares.
+    pub timeout: Option<Duration>,
+}
+
+/// What the server answered, whatever the status. Redirects should already
+/// have been followed.
+pub struct FetchResponse {
+    pub status: u16,
+    /// Response headers. Only self-updates look at them, for the
+    /// `Content-Length`, `ETag` and `Last-Modified` of a download.
+    pub headers: Vec<(String, String)>,
+    pub body: Box<dyn Read + Send>,
+}
+
+impl FetchResponse {
+    /// A response with no headers.
+    pub fn new(status: u16, body: impl Read + Send + 'static) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Box::new(body),
+        }
+    }
+}
+
+/// Sends every HTTP request the crate makes with `fetch` instead of its own
+/// backend, for tools that already have an HTTP stack. Built without any
+/// backend feature (`default-features = false`), the crate has no HTTP or
+/// TLS dependency and can only reach the network this way.
+///
+/// This applies to the whole process, and replaces any function set before.
+/// An `Err` is treated like a connection failure.
+///
+/// ```
+/// use moz_cli_version_check::{set_fetch, FetchResponse};
+///
+/// set_fetch(|request| {
+///     // Hand the request to the tool's own HTTP client here.
+///     if request.url.ends_with("/manifest.json") {
+///         let body = br#"{"version": "2.0.0"}"#;
+///         Ok(FetchResponse::new(200, &body[..]))
+///     } else {
+///         Err(format!("not fetching {}", request.url))
+///     }
+/// });
+/// ```
+pub fn set_fetch(
+    fetch: impl Fn(&FetchRequest) -> Result<FetchResponse, String> + Send + Sync + 'static,
+) {
+    *FETCH.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(fetch));
+}
+
+/// The function given to [`set_fetch`], if any.
+pub(super) fn registered() -> Option<Arc<FetchFn>> {
+    FETCH.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub(super) fn send(fetch: &FetchFn, request: Request) -> Result<Response, Error> {
+    let request = FetchRequest {
+        method: match request.method {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+        },
+        url: request.url,
+        headers: request
+            .headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        timeout: request.timeout,
+    };
+    let response = fetch(&request).map_err(Error::new)?;
+    Ok(Response {
+        status: response.status,
+        #[cfg(feature = "self-update-download")]
+        headers: response
+            .headers
+            .into_iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), value))
+            .collect(),
+        body: response.body,
+    })
+}
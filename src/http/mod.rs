@@ -0,0 +1,252 @@
+//! The few HTTP requests the crate makes, sent with the function given to
+//! [`set_fetch`] when there is one, or else whichever backend is enabled:
+//! `reqwest` by default, the lighter `ureq`, or the system's libcurl with
+//! `curl`. When several are, `curl` wins over `ureq`, which wins over
+//! `reqwest`. In the browser, `browser` sends them with XMLHttpRequest.
+
+use std::io::{self, Read};
+use std::time::Duration;
+
+use crate::json;
+
+mod dns;
+mod fetch;
+mod rate_limit;
+pub use dns::{set_dns, DnsConfig};
+pub use fetch::{set_fetch, FetchRequest, FetchResponse};
+pub use rate_limit::set_rate_limit;
+
+#[cfg(feature = "curl")]
+mod curl_backend;
+#[cfg(feature = "curl")]
+use curl_backend as backend;
+
+#[cfg(all(feature = "ureq", not(feature = "curl")))]
+mod ureq_backend;
+#[cfg(all(feature = "ureq", not(feature = "curl")))]
+use ureq_backend as backend;
+
+#[cfg(all(feature = "reqwest", not(any(feature = "ureq", feature = "curl"))))]
+mod reqwest_backend;
+#[cfg(all(feature = "reqwest", not(any(feature = "ureq", feature = "curl"))))]
+use reqwest_backend as backend;
+
+#[cfg(all(
+    feature = "browser",
+    target_arch = "wasm32",
+    not(any(feature = "reqwest", feature = "ureq", feature = "curl"))
+))]
+mod xhr_backend;
+#[cfg(all(
+    feature = "browser",
+    target_arch = "wasm32",
+    not(any(feature = "reqwest", feature = "ureq", feature = "curl"))
+))]
+use xhr_backend as backend;
+
+/// With no backend, only a function given to `set_fetch` reaches the network.
+#[cfg(not(any(
+    feature = "reqwest",
+    feature = "ureq",
+    feature = "curl",
+    all(feature = "browser", target_arch = "wasm32")
+)))]
+mod backend {
+    use super::{Error, Request, Response};
+
+    pub(super) fn send(request: Request) -> Result<Response, Error> {
+        Err(Error::new(format!(
+            "no HTTP backend to fetch {}: call set_fetch first",
+            request.url
+        )))
+    }
+}
+
+/// The backend requests go through, or `None` when only a function given to
+/// [`set_fetch`] can send them.
+pub(crate) fn backend_name() -> Option<&'static str> {
+    if cfg!(feature = "curl") {
+        Some("curl")
+    } else if cfg!(feature = "ureq") {
+        Some("ureq")
+    } else if cfg!(feature = "reqwest") {
+        Some("reqwest")
+    } else if cfg!(all(feature = "browser", target_arch = "wasm32")) {
+        Some("XMLHttpRequest")
+    } else {
+        None
+    }
+}
+
+/// Whether a function given to [`set_fetch`] sends requests instead of the
+/// backend.
+pub(crate) fn fetch_registered() -> bool {
+    fetch::registered().is_some()
+}
+
+/// The addresses of `host`, resolved as requests resolve it.
+#[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+pub(crate) fn resolve(host: &str, port: u16) -> io::Result<Vec<std::net::IpAddr>> {
+    dns::config().unwrap_or_default().resolve(host, port)
+}
+
+/// How long connecting to a server may take, whatever the request.
+#[cfg(any(feature = "reqwest", feature = "ureq", feature = "curl"))]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a request failed: the server couldn't be reached, or answered with an
+/// error status.
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    fn new(message: impl Into<String>) -> Self {
+        Error(message.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Method {
+    Get,
+    Head,
+}
+
+/// Sends requests on behalf of a tool, identified by their `User-Agent`.
+#[derive(Debug, Clone)]
+pub(crate) struct Client {
+    user_agent: String,
+    timeout: Option<Duration>,
+    rate_limited: bool,
+}
+
+impl Client {
+    /// Requests give up after `timeout`, or the backend's default when
+    /// `None`.
+    pub(crate) fn new(user_agent: String, timeout: Option<Duration>) -> Self {
+        Self {
+            user_agent,
+            timeout,
+            rate_limited: false,
+        }
+    }
+
+    /// Counts requests against the limit given to [`set_rate_limit`].
+    pub(crate) fn rate_limited(mut self) -> Self {
+        self.rate_limited = true;
+        self
+    }
+
+    pub(crate) fn get(&self, url: &str) -> Request {
+        self.request(Method::Get, url)
+    }
+
+    pub(crate) fn head(&self, url: &str) -> Request {
+        self.request(Method::Head, url)
+    }
+
+    fn request(&self, method: Method, url: &str) -> Request {
+        Request {
+            method,
+            url: url.to_string(),
+            headers: vec![("User-Agent", self.user_agent.clone())],
+            timeout: self.timeout,
+            rate_limited: self.rate_limited,
+        }
+    }
+}
+
+pub(crate) struct Request {
+    method: Method,
+    url: String,
+    headers: Vec<(&'static str, String)>,
+    timeout: Option<Duration>,
+    rate_limited: bool,
+}
+
+impl Request {
+    pub(crate) fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub(crate) fn send(self) -> Result<Response, Error> {
+        if self.rate_limited && !rate_limit::take() {
+            return Err(Error::new(format!(
+                "request rate limit reached, not fetching {}",
+                self.url
+            )));
+        }
+        if let Some(fetch) = fetch::registered() {
+            return fetch::send(&*fetch, self);
+        }
+        backend::send(self)
+    }
+}
+
+/// A response whatever its status, with its body yet to be read.
+pub(crate) struct Response {
+    status: u16,
+    /// Only self-updates look at headers.
+    #[cfg(feature = "self-update-download")]
+    headers: Vec<(String, String)>,
+    body: Box<dyn Read + Send>,
+}
+
+impl Response {
+    pub(crate) fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub(crate) fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The response, or an error when its status is one.
+    pub(crate) fn error_for_status(self) -> Result<Self, Error> {
+        if self.status >= 400 {
+            Err(Error::new(format!("HTTP status {}", self.status)))
+        } else {
+            Ok(self)
+        }
+    }
+
+    #[cfg(feature = "self-update-download")]
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[cfg(feature = "self-update-download")]
+    pub(crate) fn content_length(&self) -> Option<u64> {
+        self.header("content-length")?.trim().parse().ok()
+    }
+
+    pub(crate) fn json<T: json::FromJson>(self) -> Result<T, Error> {
+        json::from_reader(self.body).map_err(|err| Error::new(err.to_string()))
+    }
+
+    #[cfg(feature = "self-update-download")]
+    pub(crate) fn text(mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        self.body
+            .read_to_string(&mut text)
+            .map_err(|err| Error::new(err.to_string()))?;
+        Ok(text)
+    }
+}
+
+impl Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
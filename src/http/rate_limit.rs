@@ -0,0 +1,50 @@
+use std::sync::Mutex;
+
+/// The registry requests the process may still make, refilled over time.
+struct Bucket {
+    per_minute: u32,
+    tokens: f64,
+    refilled_at: u64,
+}
+
+static BUCKET: Mutex<Option<Bucket>> = Mutex::new(None);
+
+/// Lets the whole process make at most `requests_per_minute` requests to
+/// release sources, however many checkers it creates and however often they
+/// check, in bursts of up to that many. Requests over the limit fail as if
+/// offline, so checks fall back to the cache. `0` stops them all, and the
+/// limit replaces any set before.
+///
+/// Meant for plugin hosts creating a checker per plugin, and test harnesses
+/// that may end up running real checks. Self-update downloads aren't
+/// counted.
+///
+/// ```
+/// moz_cli_version_check::set_rate_limit(10);
+/// ```
+pub fn set_rate_limit(requests_per_minute: u32) {
+    *BUCKET.lock().unwrap_or_else(|e| e.into_inner()) = Some(Bucket {
+        per_minute: requests_per_minute,
+        tokens: f64::from(requests_per_minute),
+        refilled_at: crate::get_current_timestamp(),
+    });
+}
+
+/// Takes a token for one request, or returns `false` if there are none
+/// left. Always succeeds when no limit was set.
+pub(crate) fn take() -> bool {
+    let mut bucket = BUCKET.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(bucket) = bucket.as_mut() else {
+        return true;
+    };
+    let now = crate::get_current_timestamp();
+    let elapsed = now.saturating_sub(bucket.refilled_at);
+    let per_minute = f64::from(bucket.per_minute);
+    bucket.tokens = (bucket.tokens + elapsed as f64 * per_minute / 60.0).min(per_minute);
+    bucket.refilled_at = now;
+    if bucket.tokens < 1.0 {
+        return false;
+    }
+    bucket.tokens -= 1.0;
+    true
+}
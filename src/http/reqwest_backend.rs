@@ -0,0 +1,114 @@
+use reqwest::blocking::Client;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+
+use super::dns::{self, DnsConfig};
+use super::{Error, Method, Request, Response, CONNECT_TIMEOUT};
+
+/// The client behind every request in the process, built on first use so
+/// that checks answered from the cache never pay for a TLS context, and
+/// shared so that later requests reuse its connections.
+static CLIENT: OnceLock<Option<Client>> = OnceLock::new();
+
+pub(super) fn send(request: Request) -> Result<Response, Error> {
+    let client = CLIENT
+        .get_or_init(|| {
+            let mut builder = Client::builder().connect_timeout(CONNECT_TIMEOUT);
+            if let Some(dns) = dns::config() {
+                builder = builder.dns_resolver(Arc::new(Resolver(dns)));
+            }
+            builder.build().ok()
+        })
+        .as_ref()
+        .ok_or_else(|| Error::new("could not create an HTTP client"))?;
+
+    let mut builder = match request.method {
+        Method::Get => client.get(&request.url),
+        Method::Head => client.head(&request.url),
+    };
+    for (name, value) in request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(timeout) = request.timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    let response = builder.send().map_err(describe)?;
+    Ok(Response {
+        status: response.status().as_u16(),
+        #[cfg(feature = "self-update-download")]
+        headers: response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: Box::new(response),
+    })
+}
+
+/// `err` with its causes, which say what actually failed, such as the TLS
+/// handshake, where reqwest's own message only says sending failed.
+fn describe(err: reqwest::Error) -> Error {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(&err);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    Error::new(message)
+}
+
+/// Resolves as [`set_dns`](crate::set_dns) was told to.
+struct Resolver(Arc<DnsConfig>);
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let (dns, host) = (Arc::clone(&self.0), name.as_str().to_string());
+        let resolution = Resolution::default();
+        let done = resolution.clone();
+        // Off the client's runtime, which a slow lookup would otherwise hold
+        // up for every other request.
+        std::thread::spawn(move || done.finish(dns.resolve(&host, 0)));
+        Box::pin(resolution)
+    }
+}
+
+type Resolved = io::Result<Vec<IpAddr>>;
+
+/// A lookup running on its own thread, and the task waiting for it.
+#[derive(Clone, Default)]
+struct Resolution(Arc<Mutex<(Option<Resolved>, Option<Waker>)>>);
+
+impl Resolution {
+    fn finish(&self, addrs: Resolved) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.0 = Some(addrs);
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for Resolution {
+    type Output = Result<Addrs, Box<dyn std::error::Error + Send + Sync>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match state.0.take() {
+            Some(addrs) => Poll::Ready(match addrs {
+                // The port is the URL's, filled in by the client.
+                Ok(addrs) => Ok(Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)))),
+                Err(err) => Err(err.into()),
+            }),
+            None => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
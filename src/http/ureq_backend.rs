@@ -0,0 +1,96 @@
+use std::sync::{Arc, OnceLock};
+use ureq::config::Config;
+use ureq::http::Uri;
+use ureq::unversioned::resolver::{self, DefaultResolver, ResolvedSocketAddrs};
+use ureq::unversioned::transport::{DefaultConnector, NextTimeout};
+use ureq::Agent;
+
+use super::dns::{self, DnsConfig};
+use super::{Error, Method, Request, Response, CONNECT_TIMEOUT};
+
+/// The agent behind every request in the process, built on first use and
+/// shared so that later requests reuse its connections.
+static AGENT: OnceLock<Agent> = OnceLock::new();
+
+pub(super) fn send(request: Request) -> Result<Response, Error> {
+    let agent = AGENT.get_or_init(|| {
+        let config = Agent::config_builder()
+            .timeout_connect(Some(CONNECT_TIMEOUT))
+            // Error statuses are told apart by the callers.
+            .http_status_as_error(false);
+        match dns::config() {
+            Some(dns) => Agent::with_parts(
+                config.timeout_resolve(dns.lookup_timeout()).build(),
+                DefaultConnector::new(),
+                Resolver(dns),
+            ),
+            None => config.build().into(),
+        }
+    });
+
+    let mut builder = match request.method {
+        Method::Get => agent.get(&request.url),
+        Method::Head => agent.head(&request.url),
+    };
+    for (name, value) in request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(timeout) = request.timeout {
+        builder = builder.config().timeout_global(Some(timeout)).build();
+    }
+
+    let response = builder.call().map_err(|err| Error::new(err.to_string()))?;
+    Ok(Response {
+        status: response.status().as_u16(),
+        #[cfg(feature = "self-update-download")]
+        headers: response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: Box::new(response.into_body().into_reader()),
+    })
+}
+
+/// Resolves as [`set_dns`](crate::set_dns) was told to, leaving what it
+/// doesn't cover to ureq's own resolver.
+struct Resolver(Arc<DnsConfig>);
+
+impl std::fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl resolver::Resolver for Resolver {
+    fn resolve(
+        &self,
+        uri: &Uri,
+        config: &Config,
+        timeout: NextTimeout,
+    ) -> Result<ResolvedSocketAddrs, ureq::Error> {
+        let port = uri
+            .port_u16()
+            .unwrap_or(if uri.scheme_str() == Some("http") {
+                80
+            } else {
+                443
+            });
+        let Some(addrs) = uri.host().and_then(|host| self.0.lookup(host)) else {
+            return DefaultResolver::default().resolve(uri, config, timeout);
+        };
+        let mut resolved = self.empty();
+        for ip in addrs {
+            if resolved
+                .try_push(std::net::SocketAddr::new(ip, port))
+                .is_err()
+            {
+                break;
+            }
+        }
+        if resolved.is_empty() {
+            return Err(ureq::Error::HostNotFound);
+        }
+        Ok(resolved)
+    }
+}
@@ -0,0 +1,40 @@
+use web_sys::XmlHttpRequest;
+
+use super::{Error, Method, Request, Response};
+
+fn js_error(err: web_sys::wasm_bindgen::JsValue) -> Error {
+    Error::new(err.as_string().unwrap_or_else(|| format!("{:?}", err)))
+}
+
+/// Sends `request` from the browser with a synchronous XMLHttpRequest, the
+/// only way to wait for a response without an async runtime. Browsers don't
+/// allow timeouts on those, nor setting `User-Agent`, so both are left out.
+pub(super) fn send(request: Request) -> Result<Response, Error> {
+    let xhr = XmlHttpRequest::new().map_err(js_error)?;
+    let method = match request.method {
+        Method::Get => "GET",
+        Method::Head => "HEAD",
+    };
+    xhr.open_with_async(method, &request.url, false)
+        .map_err(js_error)?;
+    for (name, value) in &request.headers {
+        let _ = xhr.set_request_header(name, value);
+    }
+    xhr.send().map_err(js_error)?;
+
+    #[cfg(feature = "self-update-download")]
+    let headers = xhr
+        .get_all_response_headers()
+        .map_err(js_error)?
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let body = xhr.response_text().map_err(js_error)?.unwrap_or_default();
+    Ok(Response {
+        status: xhr.status().map_err(js_error)?,
+        #[cfg(feature = "self-update-download")]
+        headers,
+        body: Box::new(std::io::Cursor::new(body.into_bytes())),
+    })
+}
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use crate::source::http_client;
+use crate::Release;
+
+/// How the running binary appears to have been installed, inferred from its
+/// location on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallMethod {
+    /// Installed with `cargo install` into the cargo bin directory.
+    Cargo,
+    /// Installed with `cargo binstall` into the cargo bin directory.
+    CargoBinstall,
+    /// Installed from a Homebrew formula.
+    Homebrew,
+    /// Installed from a Scoop bucket.
+    Scoop,
+    /// Installed with `winget`.
+    Winget,
+    /// Installed by `./mach bootstrap` under `~/.mozbuild`.
+    MozBuild,
+    /// Installed by the system package manager.
+    SystemPackage,
+    Unknown,
+}
+
+impl InstallMethod {
+    /// Inspects the path of the running executable to guess how it was
+    /// installed.
+    pub fn detect() -> Self {
+        std::env::current_exe()
+            .ok()
+            .map(|exe| Self::from_path(&exe))
+            .unwrap_or(InstallMethod::Unknown)
+    }
+
+    /// Guesses how the executable at `exe` was installed, from its location,
+    /// e.g. to update another tool than the running one.
+    pub fn from_path(exe: &Path) -> Self {
+        let resolved = exe.canonicalize().unwrap_or_else(|_| exe.to_path_buf());
+
+        if resolved.components().any(|c| c.as_os_str() == "Cellar") {
+            return InstallMethod::Homebrew;
+        }
+
+        let components: Vec<_> = resolved
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+            .collect();
+        if components
+            .windows(2)
+            .any(|pair| pair[0] == "scoop" && (pair[1] == "apps" || pair[1] == "shims"))
+        {
+            return InstallMethod::Scoop;
+        }
+        if components
+            .windows(2)
+            .any(|pair| pair[0] == "winget" && (pair[1] == "packages" || pair[1] == "links"))
+        {
+            return InstallMethod::Winget;
+        }
+
+        if let Some(home) = dirs::home_dir() {
+            if resolved.starts_with(home.join(".mozbuild")) {
+                return InstallMethod::MozBuild;
+            }
+        }
+
+        if let Some(cargo_home) = cargo_home() {
+            if resolved.starts_with(cargo_home.join("bin")) {
+                let tool = resolved
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default();
+                return if installed_by_binstall(&cargo_home, tool) {
+                    InstallMethod::CargoBinstall
+                } else {
+                    InstallMethod::Cargo
+                };
+            }
+        }
+
+        if ["/usr/bin", "/bin", "/usr/sbin", "/sbin"]
+            .iter()
+            .any(|dir| resolved.parent() == Some(Path::new(dir)))
+        {
+            return InstallMethod::SystemPackage;
+        }
+
+        InstallMethod::Unknown
+    }
+
+    /// The upgrade command that fits this install method, if there is one.
+    pub fn suggested_command(self) -> Option<&'static str> {
+        match self {
+            InstallMethod::Cargo => Some("cargo install {tool}"),
+            InstallMethod::CargoBinstall => Some("cargo binstall {tool}"),
+            InstallMethod::Homebrew => Some("brew upgrade {tool}"),
+            InstallMethod::Scoop => Some("scoop update {tool}"),
+            InstallMethod::Winget => Some("winget upgrade {tool}"),
+            InstallMethod::MozBuild => Some("./mach bootstrap"),
+            InstallMethod::SystemPackage | InstallMethod::Unknown => None,
+        }
+    }
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+fn installed_by_binstall(cargo_home: &Path, tool: &str) -> bool {
+    std::fs::read_to_string(cargo_home.join("binstall").join("crates-v1.json"))
+        .map(|content| content.contains(&format!("\"name\":\"{}\"", tool)))
+        .unwrap_or(false)
+}
+
+/// Looks `name` up on the `PATH`, the way a shell would.
+pub(crate) fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Where cargo-binstall falls back to when a crate doesn't publish its own
+/// prebuilt binaries.
+const QUICKINSTALL_URL: &str = "https://github.com/cargo-bins/cargo-quickinstall/releases/download";
+
+/// Whether `cargo binstall` can fetch a prebuilt binary of `release` for this
+/// target rather than building it from source: the release has an asset named
+/// after the target triple, or cargo-quickinstall built one. `None` when that
+/// can't be told, e.g. offline.
+pub(crate) fn prebuilt_available(tool: &str, release: &Release) -> Option<bool> {
+    let triple = crate::target_triple();
+    if release
+        .assets
+        .iter()
+        .any(|asset| asset.name.contains(&triple))
+    {
+        return Some(true);
+    }
+
+    let url = format!(
+        "{}/{tool}-{version}/{tool}-{version}-{triple}.tar.gz",
+        QUICKINSTALL_URL,
+        tool = tool,
+        version = release.version,
+        triple = triple
+    );
+    let response = http_client(tool).head(&url).send().ok()?;
+    if response.is_success() {
+        Some(true)
+    } else if response.status() == 404 {
+        Some(false)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,160 @@
+//! Reading and writing the JSON of the cache and of registry responses.
+//!
+//! By default this goes through serde's derived implementations. With the
+//! `tiny` feature, values are parsed into a `serde_json::Value` and picked
+//! apart by hand instead, so none of the code serde derives ends up in the
+//! binary, and the cache is written without indentation.
+
+#[cfg(not(feature = "tiny"))]
+use std::io::Read;
+
+#[cfg(not(feature = "tiny"))]
+pub(crate) use serde::de::DeserializeOwned as FromJson;
+#[cfg(not(feature = "tiny"))]
+pub(crate) use serde::Serialize as ToJson;
+
+#[cfg(not(feature = "tiny"))]
+pub(crate) fn from_reader<T: FromJson>(reader: impl Read) -> Result<T, serde_json::Error> {
+    serde_json::from_reader(reader)
+}
+
+#[cfg(not(feature = "tiny"))]
+pub(crate) fn from_str<T: FromJson>(text: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(text)
+}
+
+#[cfg(not(feature = "tiny"))]
+pub(crate) fn to_string<T: ToJson>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string(value)
+}
+
+/// Pretty-printed, as the cache is meant to be readable.
+#[cfg(not(feature = "tiny"))]
+pub(crate) fn to_file_string<T: ToJson>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(value)
+}
+
+#[cfg(feature = "tiny")]
+pub(crate) use tiny::*;
+
+#[cfg(feature = "tiny")]
+mod tiny {
+    use serde_json::{Map, Value};
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    /// Converts a parsed JSON value, or fails with `None` when it doesn't
+    /// have the expected shape.
+    pub(crate) trait FromJson: Sized {
+        fn from_json(value: Value) -> Option<Self>;
+    }
+
+    pub(crate) trait ToJson {
+        fn to_json(&self) -> Value;
+    }
+
+    fn shape_error() -> serde_json::Error {
+        serde::de::Error::custom("unexpected JSON structure")
+    }
+
+    pub(crate) fn from_reader<T: FromJson>(reader: impl Read) -> Result<T, serde_json::Error> {
+        T::from_json(serde_json::from_reader(reader)?).ok_or_else(shape_error)
+    }
+
+    pub(crate) fn from_str<T: FromJson>(text: &str) -> Result<T, serde_json::Error> {
+        T::from_json(serde_json::from_str(text)?).ok_or_else(shape_error)
+    }
+
+    pub(crate) fn to_string<T: ToJson>(value: &T) -> Result<String, serde_json::Error> {
+        Ok(value.to_json().to_string())
+    }
+
+    pub(crate) fn to_file_string<T: ToJson>(value: &T) -> Result<String, serde_json::Error> {
+        to_string(value)
+    }
+
+    /// The fields of a JSON object, taken out one at a time.
+    pub(crate) struct Object(Map<String, Value>);
+
+    impl Object {
+        pub(crate) fn new(value: Value) -> Option<Self> {
+            match value {
+                Value::Object(fields) => Some(Object(fields)),
+                _ => None,
+            }
+        }
+
+        /// A field that must be there, unless `T` is an `Option`.
+        pub(crate) fn take<T: FromJson>(&mut self, key: &str) -> Option<T> {
+            T::from_json(self.0.remove(key).unwrap_or(Value::Null))
+        }
+
+        /// A field that defaults when missing or `null`.
+        pub(crate) fn take_or_default<T: FromJson + Default>(&mut self, key: &str) -> Option<T> {
+            match self.0.remove(key) {
+                None | Some(Value::Null) => Some(T::default()),
+                Some(value) => T::from_json(value),
+            }
+        }
+
+        /// The fields left, for objects keyed by name.
+        pub(crate) fn into_fields(self) -> Map<String, Value> {
+            self.0
+        }
+    }
+
+    impl FromJson for String {
+        fn from_json(value: Value) -> Option<Self> {
+            match value {
+                Value::String(text) => Some(text),
+                _ => None,
+            }
+        }
+    }
+
+    impl FromJson for bool {
+        fn from_json(value: Value) -> Option<Self> {
+            value.as_bool()
+        }
+    }
+
+    impl FromJson for u64 {
+        fn from_json(value: Value) -> Option<Self> {
+            value.as_u64()
+        }
+    }
+
+    impl FromJson for u32 {
+        fn from_json(value: Value) -> Option<Self> {
+            value.as_u64()?.try_into().ok()
+        }
+    }
+
+    impl<T: FromJson> FromJson for Option<T> {
+        fn from_json(value: Value) -> Option<Self> {
+            match value {
+                Value::Null => Some(None),
+                value => T::from_json(value).map(Some),
+            }
+        }
+    }
+
+    impl<T: FromJson> FromJson for Vec<T> {
+        fn from_json(value: Value) -> Option<Self> {
+            match value {
+                Value::Array(items) => items.into_iter().map(T::from_json).collect(),
+                _ => None,
+            }
+        }
+    }
+
+    impl<T: FromJson> FromJson for HashMap<String, T> {
+        fn from_json(value: Value) -> Option<Self> {
+            Object::new(value)?
+                .into_fields()
+                .into_iter()
+                .map(|(key, value)| Some((key, T::from_json(value)?)))
+                .collect()
+        }
+    }
+}
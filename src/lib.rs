@@ -1,22 +1,121 @@
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
-use std::sync::mpsc::Receiver;
-use std::sync::Mutex;
-use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "clap")]
+mod cli;
+mod config;
+mod console;
+mod doctor;
+mod future;
+mod helper;
+mod http;
+mod install;
+mod json;
+mod locale;
+mod message;
+mod notifier;
+mod platform;
+mod prompt;
+mod self_update;
+mod shell_hook;
+mod source;
+mod store;
+#[cfg(feature = "test-util")]
+mod test_util;
+mod version;
+
+#[cfg(feature = "clap")]
+pub use cli::{
+    check_update_arg, format_span, handle_check_update, run_self_command, self_command,
+    EXIT_UPDATE_AVAILABLE,
+};
+pub use config::{
+    config_path, pin_tool, pinned_tools, policy_path, unpin_tool, validate_config, ConfigLayer,
+    ConfigValidation,
+};
+pub use doctor::{diagnose, Diagnostic, Health};
+pub use future::StatusFuture;
+pub use http::{set_dns, set_fetch, set_rate_limit, DnsConfig, FetchRequest, FetchResponse};
+pub use install::InstallMethod;
+pub use locale::Translation;
+#[cfg(feature = "desktop-notification")]
+pub use notifier::DesktopNotifier;
+pub use notifier::Notifier;
+pub use platform::{target_triple, Platform};
+pub use self_update::{
+    SelfUpdateError, SelfUpdateOutcome, SelfUpdatePlan, UpdateMethod, UpdateProgress,
+};
+pub use semver::{Version, VersionReq};
+pub use shell_hook::{shell_hook, Shell};
+pub use source::{
+    Asset, BlessedVersions, CratesIo, CratesIoVersion, FileManifest, GitHubReleases,
+    MozillaArtifacts, Release, Source, SourceError,
+};
+pub use store::{set_cache_per_tool, set_cache_store, CacheStore};
+#[cfg(feature = "test-util")]
+pub use test_util::{CrateFixture, MockRegistry};
+use version::VersionPolicy;
+pub use version::{normalize_version, VersionOrdering, VersionParseError, VersionScheme};
 
 const DEFAULT_CHECK_INTERVAL_SECONDS: u64 = 86400;
-const CHECK_TIMEOUT_SECONDS: u64 = 5;
+pub(crate) const CHECK_TIMEOUT_SECONDS: u64 = 5;
 const UPDATE_CHECK_INTERVAL_ENV: &str = "MOZTOOLS_UPDATE_CHECK_INTERVAL_SECONDS";
+const ACCESSIBLE_ENV: &str = "MOZTOOLS_ACCESSIBLE";
+const VERBOSITY_ENV: &str = "MOZTOOLS_UPDATE_VERBOSITY";
 
-#[derive(Debug, Serialize, Deserialize)]
+const BINSTALL_HINT: &str = "Tip: install cargo-binstall to update from prebuilt binaries: \
+                             https://github.com/cargo-bins/cargo-binstall\n";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct ToolVersionInfo {
     last_check: u64,
     #[serde(default)]
     latest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    published_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    binstall_hint_shown: bool,
+    /// The running version, when it was newer than `latest` at the last
+    /// check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ahead: Option<String>,
+    /// Every version the source listed, to count how far behind the running
+    /// one is.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    versions: Vec<String>,
+    /// Whether cargo-binstall can fetch a prebuilt binary of `latest` for
+    /// this target, when that was checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prebuilt: Option<bool>,
+    /// How many lookups in a row found `latest`.
+    #[serde(default)]
+    sightings: u32,
+    /// The version running at the last check, and since when it has been.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    running: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    running_since: Option<u64>,
+    /// When the notice that `running` is getting old was last shown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stale_notice_shown: Option<u64>,
+    /// When a helper process was last started to refresh this entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    helper_started: Option<u64>,
+    /// Until when the user silenced the tool's notices, with
+    /// [`snooze_tool`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snoozed_until: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -25,22 +124,563 @@ struct VersionCache {
     tools: HashMap<String, ToolVersionInfo>,
 }
 
-#[derive(Debug, Deserialize)]
-struct CratesIoResponse {
-    #[serde(rename = "crate")]
-    crate_info: CrateInfo,
+#[cfg(feature = "tiny")]
+impl json::FromJson for ToolVersionInfo {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(ToolVersionInfo {
+            last_check: object.take("last_check")?,
+            latest: object.take_or_default("latest")?,
+            url: object.take("url")?,
+            published_at: object.take("published_at")?,
+            notes: object.take("notes")?,
+            binstall_hint_shown: object.take_or_default("binstall_hint_shown")?,
+            ahead: object.take("ahead")?,
+            versions: object.take_or_default("versions")?,
+            prebuilt: object.take("prebuilt")?,
+            sightings: object.take_or_default("sightings")?,
+            running: object.take("running")?,
+            running_since: object.take("running_since")?,
+            stale_notice_shown: object.take("stale_notice_shown")?,
+            helper_started: object.take("helper_started")?,
+            snoozed_until: object.take("snoozed_until")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::ToJson for ToolVersionInfo {
+    fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value;
+
+        let mut fields = serde_json::Map::new();
+        let mut put = |key: &str, value: Value| {
+            if !value.is_null() {
+                fields.insert(key.to_string(), value);
+            }
+        };
+        put("last_check", self.last_check.into());
+        put("latest", self.latest.clone().into());
+        put("url", self.url.clone().into());
+        put("published_at", self.published_at.into());
+        put("notes", self.notes.clone().into());
+        if self.binstall_hint_shown {
+            put("binstall_hint_shown", true.into());
+        }
+        put("ahead", self.ahead.clone().into());
+        if !self.versions.is_empty() {
+            put("versions", self.versions.clone().into());
+        }
+        put("prebuilt", self.prebuilt.into());
+        put("sightings", self.sightings.into());
+        put("running", self.running.clone().into());
+        put("running_since", self.running_since.into());
+        put("stale_notice_shown", self.stale_notice_shown.into());
+        put("helper_started", self.helper_started.into());
+        put("snoozed_until", self.snoozed_until.into());
+        Value::Object(fields)
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for VersionCache {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        Some(VersionCache {
+            tools: json::FromJson::from_json(value)?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::ToJson for VersionCache {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.tools
+                .iter()
+                .map(|(tool, info)| (tool.clone(), info.to_json()))
+                .collect(),
+        )
+    }
+}
+
+/// How far the latest release is from the running version.
+///
+/// Follows cargo's compatibility rules: a change in the leftmost non-zero
+/// component is a `Major` bump, so `0.1.4` → `0.2.0` is `Major` and
+/// `0.1.4` → `0.1.5` is `Minor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// `"patch"`, `"minor"` or `"major"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+/// Where the update notice is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Output {
+    #[default]
+    Stderr,
+    Stdout,
+    /// Never print anything; [`VersionChecker::wait_for_update`] still works.
+    Suppressed,
+}
+
+/// How the update notice is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoticeFormat {
+    /// Human-readable prose.
+    #[default]
+    Text,
+    /// A single JSON line for wrapper scripts and editors, e.g.
+    /// `{"type":"update-available","tool":"socorro-cli","current":"0.1.0","latest":"0.2.0",...}`.
+    Json,
+    /// The prose notice drawn in a bordered box, wrapped to the terminal
+    /// width, so it stands out at the end of a long log.
+    Boxed,
+}
+
+/// Which characters the notice may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// Unicode when the locale says the terminal uses UTF-8, ASCII otherwise.
+    #[default]
+    Auto,
+    /// Box drawing, arrows and accented letters as written.
+    Unicode,
+    /// Pure ASCII: box borders use `+-|`, and templates and translations are
+    /// folded to their closest ASCII spelling.
+    Ascii,
+}
+
+/// When the notice is shown relative to the host's own output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Placement {
+    /// Shown by [`VersionChecker::print_warning`], normally just before exit.
+    #[default]
+    Exit,
+    /// Shown by [`VersionChecker::check_async`] from the cached result,
+    /// before the host prints anything. When the cache has nothing to report,
+    /// a newer version found by the background check is still shown by
+    /// `print_warning`.
+    ///
+    /// This reads the cache on the calling thread, which `Exit` never does.
+    Startup,
+    /// Shown by [`VersionChecker::check_async`] from the cached result, like
+    /// `Startup`, but never by `print_warning`: a newer version found by the
+    /// background check is only reported by the next run, so the check can't
+    /// delay or interleave with this run's output.
+    ///
+    /// The lookup is left to the [helper process](VersionChecker::refresh_in_helper),
+    /// which outlives this run, so `check_async` must be called first thing.
+    NextRun,
+}
+
+/// What to do when the running version is a development build, such as
+/// `1.2.3-dev`, `1.2.3+git.abc123` or `1.2.3-4-gabc123-dirty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DevBuildPolicy {
+    /// Check it like any other version.
+    #[default]
+    Check,
+    /// Don't check for updates at all.
+    Skip,
+    /// Check the version it's based on, with the markers removed.
+    CompareBase,
+    /// Like `CompareBase`, and say in the notice that this is a development
+    /// build.
+    Annotate,
+}
+
+/// How much detail the prose notice contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// A single line.
+    Quiet,
+    /// The headline, release age, update command and release URL.
+    #[default]
+    Normal,
+    /// Everything in `Normal`, plus the version gap and an excerpt of the
+    /// release notes when the source provides them.
+    Verbose,
+}
+
+impl Verbosity {
+    fn from_env() -> Option<Self> {
+        match std::env::var(VERBOSITY_ENV)
+            .ok()?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "quiet" => Some(Verbosity::Quiet),
+            "normal" => Some(Verbosity::Normal),
+            "verbose" => Some(Verbosity::Verbose),
+            _ => None,
+        }
+    }
+}
+
+/// How long the background check took, for hosts profiling their startup
+/// or tuning how long to wait for it. See [`VersionChecker::check_report`].
+///
+/// Durations are zero in browsers, which give wasm no clock to measure them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UpdateCheckReport {
+    /// Reading and parsing the cache, every time it was read.
+    pub cache_read: Duration,
+    /// Asking the source, or `None` if the cache was recent enough.
+    pub fetch: Option<Duration>,
+    /// The whole check, cache writes and any check for prebuilt binaries
+    /// included.
+    pub total: Duration,
+}
+
+/// A newer release that the user should hear about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateInfo {
+    pub tool: String,
+    pub current: String,
+    pub latest: String,
+    pub bump: Bump,
+    /// Page describing the latest release, when the source provides one.
+    pub url: Option<String>,
+    /// When the latest release was published, in seconds since the Unix
+    /// epoch, when the source provides it.
+    pub published_at: Option<u64>,
+    /// An excerpt of the release notes, when the source provides them.
+    pub notes: Option<String>,
+    /// How many stable releases separate the running version from the
+    /// latest one, counting the latest, when the source lists its versions.
+    pub versions_behind: Option<usize>,
+}
+
+/// What the last check concluded, as returned by
+/// [`VersionChecker::wait_for_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateStatus {
+    /// The running version is the latest one.
+    UpToDate,
+    /// A newer release is available.
+    Available(UpdateInfo),
+    /// The running version is newer than the latest published release: a
+    /// local development build, or the latest release was yanked.
+    AheadOfRegistry { current: String, latest: String },
+}
+
+/// What a check found, before it is filtered into an [`UpdateStatus`].
+#[derive(Debug, Clone)]
+enum Checked {
+    Update(Release),
+    Ahead { latest: String },
+    Current,
+}
+
+impl Checked {
+    fn into_update(self) -> Option<Release> {
+        match self {
+            Checked::Update(release) => Some(release),
+            Checked::Ahead { .. } | Checked::Current => None,
+        }
+    }
+}
+
+impl UpdateInfo {
+    /// The latest version, parsed after [`normalize_version`], so hosts can
+    /// match it against a [`VersionReq`] without re-parsing it themselves.
+    /// `None` when it isn't a semver version; a fourth component is dropped.
+    ///
+    /// ```
+    /// # fn check(update: moz_cli_version_check::UpdateInfo) {
+    /// use moz_cli_version_check::VersionReq;
+    ///
+    /// let supported = VersionReq::parse(">=2.0, <3").unwrap();
+    /// if update.latest_parsed().is_some_and(|latest| supported.matches(&latest)) {
+    ///     // Safe to suggest the update.
+    /// }
+    /// # }
+    /// ```
+    pub fn latest_parsed(&self) -> Option<Version> {
+        version::parse(&self.latest).map(|parsed| parsed.version)
+    }
+
+    /// The running version, parsed like [`latest_parsed`](Self::latest_parsed).
+    pub fn current_parsed(&self) -> Option<Version> {
+        version::parse(&self.current).map(|parsed| parsed.version)
+    }
+}
+
+/// The placeholder values worked out from an [`UpdateInfo`] rather than
+/// borrowed from it, shared by the notice and the update command it shows.
+struct TemplateDetails {
+    url: String,
+    age: String,
+    behind: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct CrateInfo {
-    max_version: String,
+impl TemplateDetails {
+    fn new(update: &UpdateInfo, translation: &Translation) -> Self {
+        Self {
+            url: update.url.clone().unwrap_or_default(),
+            age: update
+                .published_at
+                .map(|published| translation.age(get_current_timestamp().saturating_sub(published)))
+                .unwrap_or_default(),
+            behind: update
+                .versions_behind
+                .map(|behind| behind.to_string())
+                .unwrap_or_default(),
+        }
+    }
 }
 
+type SuspendFn = Box<dyn Fn(&mut dyn FnMut()) + Send + Sync>;
+
 pub struct VersionChecker {
     tool_name: String,
     current_version: String,
     check_interval: Duration,
-    receiver: Mutex<Option<Receiver<Option<String>>>>,
+    message_template: Option<String>,
+    ahead_message: Option<String>,
+    update_command: Option<String>,
+    platform_update_commands: HashMap<Platform, String>,
+    fallback_update_command: Option<String>,
+    binstall_hint: bool,
+    verify_binstall: bool,
+    min_bump: Bump,
+    min_release_age: Duration,
+    min_sightings: u32,
+    stale_after: Option<Duration>,
+    use_helper: bool,
+    cache_in_background: bool,
+    version_policy: VersionPolicy,
+    output: Output,
+    notice_format: NoticeFormat,
+    charset: Charset,
+    accessible: bool,
+    verbosity: Verbosity,
+    prompt_to_update: bool,
+    placement: Placement,
+    dev_builds: DevBuildPolicy,
+    suspend_output: Option<SuspendFn>,
+    shown: AtomicBool,
+    locale: Option<String>,
+    translations: HashMap<String, Translation>,
+    notifier: Option<Box<dyn Notifier>>,
+    source: Arc<dyn Source>,
+    update_options: self_update::UpdateOptions,
+    state: Mutex<CheckState>,
+}
+
+/// The in-flight background check, and its outcome once received.
+#[derive(Default)]
+struct CheckState {
+    receiver: Option<Receiver<Outcome>>,
+    result: Option<Outcome>,
+    handle: Option<JoinHandle<()>>,
+    waker: future::WakerSlot,
+}
+
+/// What the background check sends back: its conclusion, and the tool's
+/// cache entry as it left it, so that printing the notice doesn't have to
+/// read the cache again.
+#[derive(Debug, Clone, Default)]
+struct Outcome {
+    checked: Option<Checked>,
+    info: Option<ToolVersionInfo>,
+    /// Whether the notice goes to the file read by the [`shell_hook`].
+    shell_notices: bool,
+    report: UpdateCheckReport,
+}
+
+impl Drop for VersionChecker {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(handle) = state.handle.take() {
+            join_if_finished(handle);
+        }
+    }
+}
+
+/// Joins the background thread if it has nothing left to do, and detaches it
+/// otherwise.
+fn join_if_finished(handle: JoinHandle<()>) {
+    if handle.is_finished() {
+        let _ = handle.join();
+    }
+}
+
+/// Runs [`VersionChecker::check_now`] for each of `checkers`, with at most
+/// `concurrency` lookups in flight, and returns what each found in the same
+/// order. The lookups share the HTTP client and its kept-alive connections,
+/// so checking several tools on the same registry costs little more than
+/// checking one; the `curl` backend doesn't keep connections between
+/// requests.
+///
+/// ```no_run
+/// use moz_cli_version_check::{check_many, VersionChecker};
+///
+/// let checkers: Vec<_> = ["searchfox-cli", "treeherder-cli", "socorro-cli"]
+///     .into_iter()
+///     .map(|tool| VersionChecker::new(tool, "0.1.0"))
+///     .collect();
+/// for update in check_many(&checkers, 4).into_iter().flatten() {
+///     println!("{} {} is available", update.tool, update.latest);
+/// }
+/// ```
+pub fn check_many(checkers: &[VersionChecker], concurrency: usize) -> Vec<Option<UpdateInfo>> {
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<UpdateInfo>>> =
+        checkers.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, checkers.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(checker) = checkers.get(index) else {
+                    break;
+                };
+                let update = checker.check_now();
+                *results[index].lock().unwrap_or_else(|e| e.into_inner()) = update;
+            });
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.into_inner().unwrap_or_else(|e| e.into_inner()))
+        .collect()
+}
+
+/// What the cache records about a tool, as listed by [`cached_tools`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedTool {
+    pub tool: String,
+    /// The latest version the last successful lookup found, if one did.
+    pub latest: Option<String>,
+    /// When the tool was last checked, in seconds since the Unix epoch.
+    pub last_check: Option<u64>,
+    /// The version of the tool that was running at its last check.
+    pub running: Option<String>,
+    /// Whether `latest` is an update for `running`, by semver.
+    pub update_available: bool,
+    /// Whether `running` was newer than `latest` at the last check.
+    pub ahead: bool,
+    /// Until when its notices are silenced by [`snooze_tool`], in seconds
+    /// since the Unix epoch.
+    pub snoozed_until: Option<u64>,
+}
+
+/// Whether the user turned update checks off, with `MOZTOOLS_UPDATE_CHECK=0`.
+/// Checkers then neither look anything up nor print any notice.
+pub fn checks_disabled() -> bool {
+    std::env::var("MOZTOOLS_UPDATE_CHECK").unwrap_or_default() == "0"
+}
+
+/// Every tool in the cache, in both the combined file and the per-tool ones
+/// of [`set_cache_per_tool`], sorted by name.
+pub fn cached_tools() -> Vec<CachedTool> {
+    let mut tools = load_cache().tools;
+    for (tool, content) in store::load_tools() {
+        if let Ok(info) = json::from_str(&content) {
+            tools.insert(tool, info);
+        }
+    }
+    let mut tools: Vec<CachedTool> = tools
+        .into_iter()
+        .map(|(tool, info)| {
+            let running = info.running.as_deref().unwrap_or_default();
+            CachedTool {
+                update_available: !running.is_empty()
+                    && VersionPolicy::default().is_update(running, &info.latest),
+                ahead: info.running.is_some() && info.ahead == info.running,
+                snoozed_until: info
+                    .snoozed_until
+                    .filter(|until| *until > get_current_timestamp()),
+                tool,
+                latest: Some(info.latest).filter(|latest| !latest.is_empty()),
+                last_check: Some(info.last_check).filter(|time| *time > 0),
+                running: info.running,
+            }
+        })
+        .collect();
+    tools.sort_by(|a, b| a.tool.cmp(&b.tool));
+    tools
+}
+
+/// Silences the notices of `tool_name` for `duration` from now, in every
+/// process, while checks go on; a zero `duration` lifts the snooze. Kept in
+/// the cache, so clearing it lifts the snooze too.
+pub fn snooze_tool(tool_name: &str, duration: Duration) {
+    let snoozed_until =
+        (!duration.is_zero()).then(|| get_current_timestamp().saturating_add(duration.as_secs()));
+    let _saving = SAVING.lock().unwrap_or_else(|e| e.into_inner());
+    // The tool may keep its entry in its own file, whatever this process
+    // does, and then never reads the combined one.
+    let own: Option<ToolVersionInfo> =
+        store::load_tool_file(tool_name).and_then(|content| json::from_str(&content).ok());
+    if let Some(mut entry) = own {
+        entry.snoozed_until = snoozed_until;
+        if let Ok(content) = json::to_file_string(&entry) {
+            store::save_tool(tool_name, &content);
+        }
+        return;
+    }
+    let mut entry = load_entry(tool_name).unwrap_or_default();
+    entry.snoozed_until = snoozed_until;
+    write_entry(tool_name, &entry);
+}
+
+/// Looks the latest release of `tool_name` up from `source` and caches it, so
+/// that the tool's first run answers from the cache instead of waiting for a
+/// lookup, e.g. when a machine is provisioned before any tool has run. What
+/// the cache tracks across lookups is kept. Returns what was found; nothing
+/// is cached when the lookup fails, or when `MOZTOOLS_FAKE_LATEST` stands in
+/// for it.
+pub fn prefetch(tool_name: &str, source: &dyn Source) -> Option<Release> {
+    if let Ok(fake) = std::env::var("MOZTOOLS_FAKE_LATEST") {
+        let url = source.release_url(tool_name, &fake);
+        let release = Release::new(fake);
+        return Some(match url {
+            Some(url) => release.with_url(url),
+            None => release,
+        });
+    }
+    let release = source.latest_release(tool_name)?;
+    let previous = load_entry(tool_name).unwrap_or_default();
+    save_entry(
+        tool_name,
+        &previous.refreshed(get_current_timestamp(), &release),
+    );
+    Some(release)
+}
+
+/// Forgets everything cached about every tool, so the next run of each
+/// checks again.
+pub fn clear_cache() {
+    store::clear();
+}
+
+/// Forgets everything cached about `tool_name`, so its next run checks
+/// again. Returns whether there was anything to forget.
+pub fn clear_cached_tool(tool_name: &str) -> bool {
+    let _saving = SAVING.lock().unwrap_or_else(|e| e.into_inner());
+    let in_file = store::remove_tool(tool_name);
+    let mut cache = load_cache();
+    let in_cache = cache.tools.remove(tool_name).is_some();
+    if in_cache {
+        if let Ok(content) = json::to_file_string(&cache) {
+            store::save(&content);
+        }
+    }
+    in_file || in_cache
 }
 
 impl VersionChecker {
@@ -61,226 +701,1816 @@ impl VersionChecker {
             tool_name: tool_name.into(),
             current_version: current_version.into(),
             check_interval,
-            receiver: Mutex::new(None),
+            message_template: None,
+            ahead_message: None,
+            update_command: None,
+            platform_update_commands: HashMap::new(),
+            fallback_update_command: None,
+            binstall_hint: false,
+            verify_binstall: false,
+            min_bump: Bump::Patch,
+            min_release_age: Duration::ZERO,
+            min_sightings: 1,
+            stale_after: None,
+            use_helper: false,
+            cache_in_background: false,
+            version_policy: VersionPolicy::default(),
+            output: Output::default(),
+            notice_format: NoticeFormat::default(),
+            charset: Charset::default(),
+            accessible: false,
+            verbosity: Verbosity::default(),
+            prompt_to_update: false,
+            placement: Placement::default(),
+            dev_builds: DevBuildPolicy::default(),
+            suspend_output: None,
+            shown: AtomicBool::new(false),
+            locale: None,
+            translations: HashMap::new(),
+            notifier: None,
+            source: Arc::new(CratesIo::new()),
+            update_options: self_update::UpdateOptions::default(),
+            state: Mutex::new(CheckState::default()),
         }
     }
 
-    pub fn check_async(&self) {
-        if std::env::var("MOZTOOLS_UPDATE_CHECK").unwrap_or_default() == "0" {
-            return;
-        }
+    /// Replaces the default two-line notice with a custom template.
+    ///
+    /// The template may use the placeholders `{tool}`, `{current}`,
+    /// `{latest}`, `{bump}`, `{command}`, `{url}`, `{age}` and `{behind}`.
+    /// Unknown placeholders are left untouched. `{bump}` is how far `{latest}`
+    /// is from `{current}`: `patch`, `minor` or `major`. `{age}` spells out
+    /// when the release was published (e.g. `3 days ago`), and is empty when
+    /// the source doesn't say; `{behind}` is [`UpdateInfo::versions_behind`],
+    /// or empty.
+    ///
+    /// ```
+    /// let checker = moz_cli_version_check::VersionChecker::new("my-tool", "0.1.0")
+    ///     .message_template("{tool} {current} → {latest}; run `{command}`");
+    /// ```
+    pub fn message_template(mut self, template: impl Into<String>) -> Self {
+        self.message_template = Some(template.into());
+        self
+    }
 
-        let (tx, rx) = std::sync::mpsc::sync_channel(1);
-        if let Ok(mut guard) = self.receiver.lock() {
-            *guard = Some(rx);
-        }
+    /// Prints `template` when the running version is newer than the latest
+    /// published release, e.g. for a local development build or after the
+    /// latest release was yanked. Nothing is printed in that case otherwise.
+    ///
+    /// The template may use the placeholders `{tool}`, `{current}`,
+    /// `{latest}` and `{command}`, the update command.
+    pub fn ahead_message(mut self, template: impl Into<String>) -> Self {
+        self.ahead_message = Some(template.into());
+        self
+    }
 
-        let tool_name = self.tool_name.clone();
-        let current_version = self.current_version.clone();
-        let check_interval = self.check_interval;
+    /// Keeps users on the version `source` reports, such as a release a team
+    /// standardized on with [`BlessedVersions`], rather than the latest one.
+    /// Any other version is a mismatch worth a notice, newer ones included,
+    /// and suggested cargo commands install that exact version. Ignores
+    /// [`min_bump`](Self::min_bump), [`min_release_age`](Self::min_release_age)
+    /// and which versions are pre-releases.
+    ///
+    /// ```no_run
+    /// use moz_cli_version_check::{BlessedVersions, VersionChecker};
+    ///
+    /// let checker = VersionChecker::new("my-tool", env!("CARGO_PKG_VERSION"))
+    ///     .blessed(BlessedVersions::url("https://example.com/tools/blessed.json"));
+    /// ```
+    pub fn blessed(mut self, source: impl Source + 'static) -> Self {
+        self.source = Arc::new(source);
+        self.version_policy.pinned = true;
+        self
+    }
 
-        thread::spawn(move || {
-            let result = check_version(&tool_name, &current_version, check_interval);
-            let _ = tx.send(result);
-        });
+    /// Looks up releases with `source` instead of crates.io.
+    pub fn source(mut self, source: impl Source + 'static) -> Self {
+        self.source = Arc::new(source);
+        self
     }
 
-    fn recv_update(&self, timeout: Duration) -> Option<String> {
-        let mut guard = self.receiver.lock().ok()?;
-        let rx = guard.as_ref()?;
-        match rx.recv_timeout(timeout) {
-            Ok(result) => {
-                *guard = None;
-                result
-            }
-            Err(_) => None,
-        }
+    /// Sets the command suggested to the user, replacing the one picked from
+    /// the detected [`InstallMethod`]. The command may use the same placeholders as
+    /// [`message_template`](Self::message_template), except `{command}`.
+    pub fn update_command(mut self, command: impl Into<String>) -> Self {
+        self.update_command = Some(command.into());
+        self
     }
 
-    /// # Clap integration
+    /// Sets the command suggested on a specific platform. It takes precedence
+    /// over [`update_command`](Self::update_command) on that platform.
+    pub fn update_command_for(mut self, platform: Platform, command: impl Into<String>) -> Self {
+        self.platform_update_commands
+            .insert(platform, command.into());
+        self
+    }
+
+    /// Registers a whole platform → command map at once, as with repeated
+    /// calls to [`update_command_for`](Self::update_command_for).
     ///
-    /// If your CLI uses clap, you must use `Parser::try_parse()` instead of
-    /// `parse()`. clap's `parse()` calls `std::process::exit()` on `--help`
-    /// and `--version`, which will skip this call entirely. With `try_parse()`,
-    /// call `e.print()` first in the `Err` branch, then `print_warning()`, then
-    /// `std::process::exit(e.exit_code())`. See the README for a full example.
-    pub fn print_warning(&self) {
-        if let Some(ref latest_version) = self.recv_update(Duration::from_millis(500)) {
-            self.print_update_message(latest_version);
-        }
+    /// ```
+    /// use moz_cli_version_check::{Platform, VersionChecker};
+    ///
+    /// let checker = VersionChecker::new("my-tool", "0.1.0").install_hints([
+    ///     (Platform::MacOs, "brew upgrade {tool}"),
+    ///     (Platform::LinuxMusl, "curl -sSf https://example.com/install-musl.sh | sh"),
+    ///     (Platform::Linux, "curl -sSf https://example.com/install.sh | sh"),
+    ///     (Platform::Windows, "winget upgrade {tool}"),
+    /// ]);
+    /// ```
+    pub fn install_hints<I, S>(mut self, hints: I) -> Self
+    where
+        I: IntoIterator<Item = (Platform, S)>,
+        S: Into<String>,
+    {
+        self.platform_update_commands.extend(
+            hints
+                .into_iter()
+                .map(|(platform, hint)| (platform, hint.into())),
+        );
+        self
     }
 
-    /// See [`print_warning`](Self::print_warning) for clap integration notes.
-    pub fn print_warning_sync(&self) {
-        if let Some(ref latest_version) = self.recv_update(Duration::from_secs(6)) {
-            self.print_update_message(latest_version);
-        }
+    /// Sets the command suggested when the default would be `cargo binstall`
+    /// but cargo-binstall isn't on the `PATH`. Defaults to
+    /// `cargo install --locked {tool}`.
+    pub fn fallback_update_command(mut self, command: impl Into<String>) -> Self {
+        self.fallback_update_command = Some(command.into());
+        self
     }
 
-    fn print_update_message(&self, latest_version: &str) {
-        eprintln!(
-            "Note: A newer version of {} is available (current: {}, latest: {})",
-            self.tool_name, self.current_version, latest_version
-        );
-        eprintln!("      Run: cargo binstall {}", self.tool_name);
+    /// When cargo-binstall had to be swapped for the fallback command, also
+    /// suggest installing it, once per tool.
+    pub fn binstall_hint(mut self, hint: bool) -> Self {
+        self.binstall_hint = hint;
+        self
     }
-}
 
-fn get_cache_path() -> Option<PathBuf> {
-    dirs::home_dir().map(|home| home.join(".mozbuild").join("tool-versions.json"))
-}
+    /// Before suggesting `cargo binstall`, checks that a prebuilt binary of
+    /// the latest release exists for this target, among the release's assets
+    /// or on cargo-quickinstall. When there is none, the fallback command is
+    /// suggested instead, as binstall would only build from source. Costs
+    /// one extra request when an update is found.
+    pub fn verify_binstall_artifacts(mut self, verify: bool) -> Self {
+        self.verify_binstall = verify;
+        self
+    }
 
-fn get_current_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0)
-}
+    /// Only report updates at least this large. With `Bump::Minor`, patch
+    /// releases are still cached but never shown to the user.
+    pub fn min_bump(mut self, bump: Bump) -> Self {
+        self.min_bump = bump;
+        self
+    }
 
-fn get_check_interval_seconds() -> u64 {
-    std::env::var(UPDATE_CHECK_INTERVAL_ENV)
-        .ok()
-        .and_then(|value| value.parse::<u64>().ok())
-        .filter(|seconds| *seconds > 0)
-        .unwrap_or(DEFAULT_CHECK_INTERVAL_SECONDS)
-}
+    /// Only report releases published at least `age` ago, so that users
+    /// aren't pushed onto a release that gets yanked hours later. Releases
+    /// whose publication time the source doesn't give are reported as usual.
+    pub fn min_release_age(mut self, age: Duration) -> Self {
+        self.min_release_age = age;
+        self
+    }
 
-fn load_cache() -> VersionCache {
-    let cache_path = match get_cache_path() {
-        Some(path) => path,
-        None => return VersionCache::default(),
-    };
+    /// Only report a release once `count` lookups in a row have found it,
+    /// smoothing over registries that briefly expose a release and then
+    /// retract it. Lookups happen at most once per check interval, so with
+    /// the default daily interval, `3` holds a release back for two days.
+    pub fn min_consistent_checks(mut self, count: u32) -> Self {
+        self.min_sightings = count;
+        self
+    }
 
-    if !cache_path.exists() {
-        return VersionCache::default();
+    /// When no update notice is printed, warns users still running a version
+    /// first checked more than `age` ago, even when lookups keep failing.
+    /// Meant for tools released often enough that a version this old is
+    /// surely outdated. Shown at most once per check interval.
+    pub fn stale_after(mut self, age: Duration) -> Self {
+        self.stale_after = Some(age);
+        self
     }
 
-    fs::read_to_string(&cache_path)
-        .ok()
-        .and_then(|content| serde_json::from_str(&content).ok())
-        .unwrap_or_default()
-}
+    /// Leaves lookups to a helper process instead of the background thread:
+    /// [`check_async`](Self::check_async) only reads the cache, and once per
+    /// check interval starts the tool's own executable again, detached and
+    /// with no arguments, to refresh it. Interactive runs then never touch
+    /// the network, and a newer version is reported on the run after the
+    /// helper found it.
+    ///
+    /// The helper returns from nowhere but `check_async`, which exits it once
+    /// the cache is refreshed, so the tool must call it before parsing its
+    /// arguments or doing anything else.
+    pub fn refresh_in_helper(mut self, helper: bool) -> Self {
+        self.use_helper = helper;
+        self
+    }
 
-fn save_cache(cache: &VersionCache) {
-    let cache_path = match get_cache_path() {
-        Some(path) => path,
-        None => return,
-    };
+    /// Leaves even reading the cache to the background thread, which
+    /// [`check_async`](Self::check_async) otherwise does right away to skip
+    /// the thread when the cache is recent. For tools run where the home
+    /// directory may be on a slow or hung network mount, which would then
+    /// delay startup.
+    pub fn read_cache_in_background(mut self, background: bool) -> Self {
+        self.cache_in_background = background;
+        self
+    }
 
-    if let Some(parent) = cache_path.parent() {
-        let _ = fs::create_dir_all(parent);
+    /// Whether `update` passes [`min_bump`](Self::min_bump) and
+    /// [`min_release_age`](Self::min_release_age).
+    fn reportable(&self, update: &UpdateInfo) -> bool {
+        if self.version_policy.pinned {
+            return true;
+        }
+        let old_enough = update.published_at.is_none_or(|published_at| {
+            get_current_timestamp().saturating_sub(published_at) >= self.min_release_age.as_secs()
+        });
+        update.bump >= self.min_bump && old_enough
     }
 
-    if let Ok(content) = serde_json::to_string_pretty(cache) {
-        let _ =
-            fs::File::create(&cache_path).and_then(|mut file| file.write_all(content.as_bytes()));
+    /// Chooses how version strings are read and ordered. Defaults to
+    /// [`VersionScheme::Semver`]; calendar-versioned tools should use
+    /// [`VersionScheme::CalVer`].
+    pub fn version_scheme(self, scheme: VersionScheme) -> Self {
+        self.version_ordering(scheme)
     }
-}
 
-fn fetch_latest_version(tool_name: &str) -> Option<String> {
-    let url = format!("https://crates.io/api/v1/crates/{}", tool_name);
+    /// Checks that the running version can be compared with the configured
+    /// [`version_scheme`](Self::version_scheme) or
+    /// [`version_ordering`](Self::version_ordering). A version that can't
+    /// is outdated as soon as the latest release is any other version, so
+    /// tools may want to assert this in their tests.
+    pub fn validate_version(&self) -> Result<(), VersionParseError> {
+        self.version_policy.ordering.validate(&self.current_version)
+    }
 
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(CHECK_TIMEOUT_SECONDS))
-        .user_agent(format!("{}/version-check", tool_name))
-        .build()
-        .ok()?;
+    /// Orders versions with `ordering`, for tools whose version scheme isn't
+    /// covered by [`VersionScheme`].
+    pub fn version_ordering(mut self, ordering: impl VersionOrdering + 'static) -> Self {
+        self.version_policy.ordering = Arc::new(ordering);
+        self
+    }
 
-    let response: CratesIoResponse = client.get(&url).send().ok()?.json().ok()?;
+    /// Also offers pre-releases such as `2.0.0-rc.1` as updates. Without this,
+    /// they are only offered to users already running a pre-release.
+    pub fn include_prereleases(mut self, include: bool) -> Self {
+        self.version_policy.prereleases = include;
+        self
+    }
 
-    Some(response.crate_info.max_version)
-}
+    /// Chooses the stream the notice is printed on. Defaults to stderr.
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
 
-fn is_newer_version(current: &str, latest: &str) -> bool {
-    let parse_version = |v: &str| -> Vec<u32> {
-        v.trim_start_matches('v')
-            .split('.')
-            .filter_map(|s| s.parse().ok())
-            .collect()
-    };
+    /// Chooses between the prose notice and a machine-readable JSON line.
+    pub fn notice_format(mut self, format: NoticeFormat) -> Self {
+        self.notice_format = format;
+        self
+    }
 
-    let current_parts = parse_version(current);
-    let latest_parts = parse_version(latest);
+    /// Controls unicode ornamentation in the notice.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
 
-    for (c, l) in current_parts.iter().zip(latest_parts.iter()) {
-        if l > c {
-            return true;
-        } else if l < c {
-            return false;
-        }
+    /// Prints the notice as a single plain sentence, without box drawing,
+    /// hyperlinks or the release URL, which screen readers handle well. Users
+    /// can also ask for this with `MOZTOOLS_ACCESSIBLE=1`.
+    pub fn accessible(mut self, accessible: bool) -> Self {
+        self.accessible = accessible;
+        self
     }
 
-    latest_parts.len() > current_parts.len()
-}
+    /// Sets how much detail the notice contains. Users can override this
+    /// with `MOZTOOLS_UPDATE_VERBOSITY=quiet|normal|verbose`.
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
 
-fn check_version(
-    tool_name: &str,
-    current_version: &str,
-    check_interval: Duration,
-) -> Option<String> {
-    if let Ok(fake) = std::env::var("MOZTOOLS_FAKE_LATEST") {
-        return if is_newer_version(current_version, &fake) {
-            Some(fake)
-        } else {
-            None
-        };
+    fn resolve_verbosity(&self) -> Verbosity {
+        Verbosity::from_env().unwrap_or(self.verbosity)
     }
 
-    let mut cache = load_cache();
-    let now = get_current_timestamp();
-    let check_interval = check_interval.as_secs();
+    /// After printing the notice in an interactive terminal, asks
+    /// `Update now? [y/N]` and runs the update command on confirmation.
+    /// Only commands running cargo, cargo-binstall, Homebrew, Scoop or winget
+    /// are offered, and never for tools installed by `./mach bootstrap`.
+    pub fn prompt_to_update(mut self, prompt: bool) -> Self {
+        self.prompt_to_update = prompt;
+        self
+    }
+
+    /// Chooses how development builds are checked; see [`DevBuildPolicy`].
+    pub fn dev_builds(mut self, policy: DevBuildPolicy) -> Self {
+        self.dev_builds = policy;
+        self
+    }
 
-    if let Some(info) = cache.tools.get(tool_name) {
-        if now.saturating_sub(info.last_check) < check_interval {
-            if is_newer_version(current_version, &info.latest) {
-                return Some(info.latest.clone());
+    /// The version to compare with the latest release: the running one, or
+    /// the one a development build is based on.
+    fn compared_version(&self) -> String {
+        match self.dev_builds {
+            DevBuildPolicy::CompareBase | DevBuildPolicy::Annotate => {
+                version::dev_build_base(&self.current_version)
             }
-            if is_newer_version(&info.latest, current_version) {
-                cache.tools.remove(tool_name);
-                save_cache(&cache);
+            DevBuildPolicy::Check | DevBuildPolicy::Skip => None,
+        }
+        .unwrap_or_else(|| self.current_version.clone())
+    }
+
+    /// Chooses whether the notice is shown at startup or at exit.
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Runs the write of the notice through `suspend`, which must call the
+    /// closure it is given exactly once. This lets hosts hide their progress
+    /// bars while the notice is printed:
+    ///
+    /// ```ignore
+    /// let multi = indicatif::MultiProgress::new();
+    /// let bars = multi.clone();
+    /// let checker = VersionChecker::new("my-tool", "0.1.0")
+    ///     .suspend_output_with(move |print| bars.suspend(print));
+    /// ```
+    pub fn suspend_output_with(
+        mut self,
+        suspend: impl Fn(&mut dyn FnMut()) + Send + Sync + 'static,
+    ) -> Self {
+        self.suspend_output = Some(Box::new(suspend));
+        self
+    }
+
+    /// Suspends the progress bars of `multi` while the notice is printed.
+    #[cfg(feature = "indicatif")]
+    pub fn multi_progress(self, multi: indicatif::MultiProgress) -> Self {
+        self.suspend_output_with(move |print| multi.suspend(print))
+    }
+
+    /// Uses the notice translation for `locale` (e.g. `fr` or `de_AT`)
+    /// instead of the one picked from `LC_ALL`, `LC_MESSAGES` or `LANG`.
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Registers a translation of the notice, overriding the built-in one for
+    /// that locale if there is one. One registered for a language is used
+    /// for all its regions, before any built-in translation.
+    ///
+    /// ```
+    /// use moz_cli_version_check::{Translation, VersionChecker};
+    /// use std::time::Duration;
+    ///
+    /// # std::env::set_var("MOZTOOLS_FAKE_LATEST", "2.0.0");
+    /// let mut french = Translation::builtin("fr").unwrap();
+    /// french.note = "Du nouveau : ".to_string();
+    /// let checker = VersionChecker::new("my-tool", "1.0.0")
+    ///     .locale("fr_CA")
+    ///     .translation("fr", french);
+    /// checker.check_async();
+    /// let notice = checker.format_warning(Duration::from_secs(5)).unwrap();
+    /// assert!(notice.contains("Du nouveau : "));
+    /// ```
+    pub fn translation(mut self, locale: impl Into<String>, translation: Translation) -> Self {
+        self.translations.insert(locale.into(), translation);
+        self
+    }
+
+    /// Also delivers the notice through `notifier`, e.g. a
+    /// `DesktopNotifier` with the `desktop-notification` feature.
+    pub fn notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifier = Some(Box::new(notifier));
+        self
+    }
+
+    fn resolve_update_command(&self) -> &str {
+        Platform::current()
+            .and_then(|platform| {
+                platform
+                    .lookup_order()
+                    .iter()
+                    .find_map(|p| self.platform_update_commands.get(p))
+            })
+            .or(self.update_command.as_ref())
+            .map(String::as_str)
+            .unwrap_or_else(|| self.default_update_command())
+    }
+
+    /// The command for the detected install method, without suggesting
+    /// `cargo binstall` to users who don't have it, or for releases it has
+    /// no prebuilt binary of.
+    fn default_update_command(&self) -> &str {
+        let suggested = InstallMethod::detect()
+            .suggested_command()
+            .unwrap_or("cargo binstall {tool}");
+        let command = if binstall_missing(suggested) || self.binstall_would_build(suggested) {
+            self.fallback_update_command
+                .as_deref()
+                .unwrap_or("cargo install --locked {tool}")
+        } else {
+            suggested
+        };
+        if !self.version_policy.pinned {
+            return command;
+        }
+        match command {
+            "cargo binstall {tool}" => "cargo binstall {tool}@{latest}",
+            "cargo install {tool}" | "cargo install --locked {tool}" => {
+                "cargo install --locked {tool}@{latest}"
             }
-            return None;
+            command => command,
         }
     }
 
-    let previous_latest = cache
-        .tools
-        .get(tool_name)
-        .map(|info| info.latest.clone())
-        .unwrap_or_default();
+    /// Whether the last check found that `suggested`, a `cargo binstall`
+    /// command, would have to build the latest release from source.
+    fn binstall_would_build(&self, suggested: &str) -> bool {
+        self.verify_binstall
+            && suggested.starts_with("cargo binstall")
+            && self
+                .checked_info()
+                .is_some_and(|info| info.prebuilt == Some(false))
+    }
 
-    cache.tools.insert(
-        tool_name.to_string(),
-        ToolVersionInfo {
-            last_check: now,
-            latest: previous_latest.clone(),
-        },
-    );
-    save_cache(&cache);
+    /// Whether the host set the update command itself, for this platform or
+    /// all of them.
+    fn explicit_update_command(&self) -> bool {
+        self.update_command.is_some()
+            || Platform::current().is_some_and(|platform| {
+                platform
+                    .lookup_order()
+                    .iter()
+                    .any(|p| self.platform_update_commands.contains_key(p))
+            })
+    }
+
+    /// Whether to check for prebuilt binaries, should `cargo binstall` be
+    /// suggested.
+    fn probe_prebuilt(&self) -> bool {
+        self.verify_binstall && !self.explicit_update_command()
+    }
+
+    /// Whether to follow the notice with a hint about cargo-binstall; records
+    /// that it was shown so it only appears once.
+    fn take_binstall_hint(&self) -> bool {
+        // Detecting the install method looks at the file system, which the
+        // flags alone often make unnecessary.
+        if !self.binstall_hint
+            || self.explicit_update_command()
+            || self
+                .checked_info()
+                .is_none_or(|info| info.binstall_hint_shown)
+        {
+            return false;
+        }
+        let suggested = InstallMethod::detect()
+            .suggested_command()
+            .unwrap_or("cargo binstall {tool}");
+        if !binstall_missing(suggested) {
+            return false;
+        }
 
-    let latest_version = match fetch_latest_version(tool_name) {
-        Some(version) => version,
-        None => {
-            if is_newer_version(current_version, &previous_latest) {
-                return Some(previous_latest);
+        self.update_checked_info(|info| info.binstall_hint_shown = true);
+        match load_entry(&self.tool_name) {
+            Some(mut info) if !info.binstall_hint_shown => {
+                info.binstall_hint_shown = true;
+                save_entry(&self.tool_name, &info);
+                true
             }
-            return None;
+            _ => false,
         }
-    };
+    }
 
-    cache.tools.insert(
-        tool_name.to_string(),
-        ToolVersionInfo {
-            last_check: now,
-            latest: latest_version.clone(),
-        },
-    );
+    /// Starts checking for a newer version in the background, to be reported
+    /// by [`print_warning`](Self::print_warning) and the like.
+    ///
+    /// Calling it again while a check is pending does nothing, so the first
+    /// check's outcome isn't lost; once that outcome has been received, it
+    /// starts a new check.
+    ///
+    /// ```
+    /// use moz_cli_version_check::{UpdateStatus, VersionChecker};
+    /// use std::time::Duration;
+    ///
+    /// # std::env::set_var("MOZTOOLS_FAKE_LATEST", "2.0.0");
+    /// let checker = VersionChecker::new("my-tool", "1.0.0");
+    /// checker.check_async();
+    /// checker.check_async();
+    /// let status = checker.wait_for_status(Duration::from_secs(5));
+    /// assert!(matches!(status, Some(UpdateStatus::Available(_))));
+    ///
+    /// checker.check_async();
+    /// let status = checker.wait_for_status(Duration::from_secs(5));
+    /// assert!(matches!(status, Some(UpdateStatus::Available(_))));
+    /// ```
+    #[cfg_attr(
+        feature = "test-util",
+        doc = r#"
+With a registry slow enough that the first check is still pending when
+`check_async` is called again, only one lookup is made, and its outcome is
+the one received:
 
-    save_cache(&cache);
+```
+use moz_cli_version_check::{CrateFixture, MockRegistry, UpdateStatus, VersionChecker};
+use std::time::Duration;
 
-    if is_newer_version(current_version, &latest_version) {
-        Some(latest_version)
-    } else {
-        None
+# let home = std::env::temp_dir().join("moz-cli-version-check-pending-check");
+# let _ = std::fs::remove_dir_all(&home);
+# std::env::set_var("HOME", &home);
+let registry = MockRegistry::start().unwrap();
+registry.publish(CrateFixture::new("slow-tool").version("1.0.0").version("1.1.0"));
+registry.delay(Duration::from_millis(500));
+registry.install();
+
+let checker = VersionChecker::new("slow-tool", "1.0.0");
+checker.check_async();
+checker.check_async();
+let status = checker.wait_for_status(Duration::from_secs(5));
+assert!(matches!(status, Some(UpdateStatus::Available(ref update)) if update.latest == "1.1.0"));
+assert_eq!(registry.requests(), ["/api/v1/crates/slow-tool"]);
+```
+"#
+    )]
+    pub fn check_async(&self) {
+        if helper::is_helper(&self.tool_name) {
+            self.check_request(Duration::ZERO).honoring_config().run();
+            std::process::exit(0);
+        }
+        if checks_disabled() {
+            return;
+        }
+        if self.dev_builds == DevBuildPolicy::Skip
+            && version::dev_build_base(&self.current_version).is_some()
+        {
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.receiver.is_some() {
+            return;
+        }
+
+        let request = self.check_request(self.check_interval);
+        // Most runs find a recent cache, which is read right away rather than
+        // paying for a thread, unless even that is left to it.
+        if !self.cache_in_background {
+            let request = request.clone().honoring_config();
+            if let Some(outcome) = request.without_thread() {
+                state.receiver = None;
+                state.result = Some(outcome);
+                drop(state);
+                if matches!(self.placement, Placement::Startup | Placement::NextRun) {
+                    self.print_cached_update();
+                }
+                return;
+            }
+        }
+
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let waker = future::WakerSlot::default();
+        let waking = future::WakeOnDrop(waker.clone());
+        let run = move || {
+            // `tx` is dropped before `waking`, so that a task woken after a
+            // panic finds the channel closed rather than still empty.
+            let _waking = waking;
+            let tx = tx;
+            let _ = tx.send(request.honoring_config().in_background());
+        };
+        #[cfg(not(all(target_family = "wasm", not(target_feature = "atomics"))))]
+        let handle = thread::Builder::new()
+            .name(format!("{}-version-check", self.tool_name))
+            .spawn(run)
+            .ok();
+        // Browsers and WASI without threads run the check in place.
+        #[cfg(all(target_family = "wasm", not(target_feature = "atomics")))]
+        let handle = {
+            run();
+            None
+        };
+        state.receiver = Some(rx);
+        state.result = None;
+        state.waker = waker;
+        if let Some(previous) = std::mem::replace(&mut state.handle, handle) {
+            join_if_finished(previous);
+        }
+        drop(state);
+
+        if matches!(self.placement, Placement::Startup | Placement::NextRun) {
+            self.print_cached_update();
+        }
+    }
+
+    /// Prints the notice for the newer version recorded in the cache, if it
+    /// is worth reporting.
+    fn print_cached_update(&self) {
+        let policy = VersionPolicy {
+            requirement: config::pin(&self.tool_name),
+            ..self.version_policy.clone()
+        };
+        if let Some(release) = cached_update(
+            &*self.source,
+            &self.tool_name,
+            &self.compared_version(),
+            &policy,
+            self.min_sightings,
+        ) {
+            let update = self.update_info(release);
+            if self.reportable(&update) {
+                self.print_update_message(&update);
+            }
+        }
+    }
+
+    fn check_request(&self, check_interval: Duration) -> CheckRequest {
+        CheckRequest {
+            source: Arc::clone(&self.source),
+            tool_name: self.tool_name.clone(),
+            current_version: self.compared_version(),
+            check_interval,
+            policy: self.version_policy.clone(),
+            probe_prebuilt: self.probe_prebuilt(),
+            min_sightings: self.min_sightings,
+            use_helper: self.use_helper || self.placement == Placement::NextRun,
+            timings: Timings::default(),
+        }
+    }
+
+    /// Returns the outcome of the background check, waiting up to `timeout`
+    /// the first time. Once received, the outcome is kept and returned
+    /// immediately by later calls.
+    fn recv_check(&self, timeout: Duration) -> Option<Checked> {
+        let mut state = self.state.lock().ok()?;
+        if let Some(ref result) = state.result {
+            return result.checked.clone();
+        }
+        let rx = state.receiver.as_ref()?;
+        // Tried first, as waiting reads a clock browsers' wasm doesn't have.
+        let received = rx.try_recv().ok();
+        match received.ok_or(()).or_else(|_| rx.recv_timeout(timeout)) {
+            Ok(result) => {
+                state.receiver = None;
+                let checked = result.checked.clone();
+                state.result = Some(result);
+                checked
+            }
+            Err(RecvTimeoutError::Timeout) => None,
+            // The check panicked: let the next `check_async` start another.
+            Err(RecvTimeoutError::Disconnected) => {
+                state.receiver = None;
+                None
+            }
+        }
+    }
+
+    /// Receives the outcome of the background check if it is there, and
+    /// otherwise has `waker` woken once it is.
+    fn poll_check(&self, waker: &std::task::Waker) -> std::task::Poll<()> {
+        use std::sync::mpsc::TryRecvError;
+        use std::task::Poll;
+
+        let Ok(mut state) = self.state.lock() else {
+            return Poll::Ready(());
+        };
+        if state.result.is_some() {
+            return Poll::Ready(());
+        }
+        let Some(rx) = state.receiver.as_ref() else {
+            return Poll::Ready(());
+        };
+        // Registered before looking, so an outcome sent in between still
+        // wakes the task.
+        state.waker.register(waker);
+        match rx.try_recv() {
+            Ok(result) => {
+                state.receiver = None;
+                state.result = Some(result);
+                Poll::Ready(())
+            }
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => {
+                state.receiver = None;
+                Poll::Ready(())
+            }
+        }
+    }
+
+    /// The tool's cache entry as the background check left it, once its
+    /// outcome has been received.
+    fn checked_info(&self) -> Option<ToolVersionInfo> {
+        self.state.lock().ok()?.result.as_ref()?.info.clone()
+    }
+
+    /// Whether the user snoozed the tool's notices with [`snooze_tool`], as
+    /// of the background check if it is done, or else as the cache says now.
+    fn snoozed(&self) -> bool {
+        self.checked_info()
+            .or_else(|| load_entry(&self.tool_name))
+            .and_then(|info| info.snoozed_until)
+            .is_some_and(|until| until > get_current_timestamp())
+    }
+
+    /// How long the background check took, once it has finished. Doesn't
+    /// wait for it: call it after [`print_warning`](Self::print_warning) or
+    /// [`shutdown`](Self::shutdown).
+    pub fn check_report(&self) -> Option<UpdateCheckReport> {
+        Some(self.state.lock().ok()?.result.as_ref()?.report)
+    }
+
+    /// Whether the user enabled shell notices for this tool, as of the
+    /// background check if it is done.
+    fn shell_notices(&self) -> bool {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| Some(state.result.as_ref()?.shell_notices))
+            .unwrap_or_else(|| shell_hook::enabled(&self.tool_name))
+    }
+
+    /// Has update notices for this tool shown by the user's shell when it
+    /// starts, through the snippet from [`shell_hook`], instead of printed by
+    /// [`print_warning`](Self::print_warning). This is the user's choice, so
+    /// it is stored for them rather than set by the tool; the `self
+    /// shell-hook` subcommand turns it on.
+    pub fn set_shell_notices(&self, enabled: bool) -> std::io::Result<()> {
+        shell_hook::set_enabled(&self.tool_name, enabled)
+    }
+
+    /// Keeps the entry returned by `checked_info` in line with what was just
+    /// written to the cache.
+    fn update_checked_info(&self, update: impl FnOnce(&mut ToolVersionInfo)) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(info) = state.result.as_mut().and_then(|r| r.info.as_mut()) {
+                update(info);
+            }
+        }
+    }
+
+    /// Whether the notice has already been printed by this checker.
+    pub fn warning_shown(&self) -> bool {
+        self.shown.load(Ordering::SeqCst)
+    }
+
+    /// Waits up to `timeout` for the background check to finish and joins its
+    /// thread, for hosts and test harnesses that want no thread left behind.
+    /// Returns whether there is none left, which is also the case when no
+    /// check was started. The outcome stays available to
+    /// [`print_warning`](Self::print_warning) and the like.
+    ///
+    /// When the deadline passes, the thread is kept and can be waited for
+    /// again. Dropping the checker joins the thread if it has finished, or
+    /// detaches it otherwise: it holds nothing of the checker's and ends on
+    /// its own once its lookup completes or times out.
+    pub fn shutdown(&self, timeout: Duration) -> bool {
+        self.recv_check(timeout);
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let Some(handle) = state.handle.take() else {
+            return true;
+        };
+        let deadline = Instant::now() + timeout;
+        // Once the outcome is sent, the thread only has to return.
+        while !handle.is_finished() && state.receiver.is_some() {
+            if Instant::now() >= deadline {
+                state.handle = Some(handle);
+                return false;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let _ = handle.join();
+        true
+    }
+
+    /// Allows the notice to be printed again by the next
+    /// [`print_warning`](Self::print_warning) call.
+    pub fn rearm(&self) {
+        self.shown.store(false, Ordering::SeqCst);
+    }
+
+    /// Asks the source whether `version` of the tool was published and can
+    /// be installed, e.g. so that a bootstrap script pinning it can suggest
+    /// `cargo install my-tool@1.4.2` knowing it will work. Not cached.
+    pub fn is_version_published(&self, version: &str) -> Result<bool, SourceError> {
+        self.source.is_published(&self.tool_name, version)
+    }
+
+    /// Asks the source right away, ignoring the check interval and
+    /// `MOZTOOLS_UPDATE_CHECK`, and refreshes the cache with the answer. For
+    /// explicit requests from the user such as a `self check` command.
+    pub fn check_now(&self) -> Option<UpdateInfo> {
+        match self.check_now_status()? {
+            UpdateStatus::Available(update) => Some(update),
+            UpdateStatus::UpToDate | UpdateStatus::AheadOfRegistry { .. } => None,
+        }
+    }
+
+    /// Like [`check_now`](Self::check_now), telling apart the outcomes the
+    /// way [`wait_for_status`](Self::wait_for_status) does: `None` means the
+    /// lookup failed, rather than that the tool is up to date.
+    pub fn check_now_status(&self) -> Option<UpdateStatus> {
+        let checked = self.check_request(Duration::ZERO).honoring_config().run()?;
+        Some(self.status(checked))
+    }
+
+    /// Waits up to `timeout` for the background check and returns the update
+    /// it found, if any, without printing anything.
+    pub fn wait_for_update(&self, timeout: Duration) -> Option<UpdateInfo> {
+        match self.wait_for_status(timeout)? {
+            UpdateStatus::Available(update) => Some(update),
+            UpdateStatus::UpToDate | UpdateStatus::AheadOfRegistry { .. } => None,
+        }
+    }
+
+    /// Waits up to `timeout` for the background check and returns what it
+    /// concluded, or `None` if it hasn't finished or the lookup failed.
+    /// Updates smaller than [`min_bump`](Self::min_bump) or more recent than
+    /// [`min_release_age`](Self::min_release_age) count as
+    /// [`UpdateStatus::UpToDate`].
+    pub fn wait_for_status(&self, timeout: Duration) -> Option<UpdateStatus> {
+        Some(self.status(self.recv_check(timeout)?))
+    }
+
+    /// What `checked` means for the host, once filtered by
+    /// [`min_bump`](Self::min_bump) and the like.
+    fn status(&self, checked: Checked) -> UpdateStatus {
+        match checked {
+            Checked::Update(release) => {
+                let update = self.update_info(release);
+                if self.reportable(&update) {
+                    UpdateStatus::Available(update)
+                } else {
+                    UpdateStatus::UpToDate
+                }
+            }
+            Checked::Ahead { latest } => UpdateStatus::AheadOfRegistry {
+                current: self.current_version.clone(),
+                latest,
+            },
+            Checked::Current => UpdateStatus::UpToDate,
+        }
+    }
+
+    /// Like [`wait_for_status`](Self::wait_for_status), for async code: the
+    /// future resolves once the background check is done, without blocking
+    /// the executor, whichever one it is.
+    ///
+    /// ```
+    /// use moz_cli_version_check::{UpdateStatus, VersionChecker};
+    ///
+    /// async fn report(checker: &VersionChecker) {
+    ///     if let Some(UpdateStatus::Available(update)) = checker.status_async().await {
+    ///         eprintln!("{} {} is available", update.tool, update.latest);
+    ///     }
+    /// }
+    /// ```
+    pub fn status_async(&self) -> StatusFuture<'_> {
+        StatusFuture { checker: self }
+    }
+
+    /// Waits up to `timeout` for the background check and returns the exact
+    /// text [`print_warning`](Self::print_warning) would print, so hosts that
+    /// buffer their output can place it themselves.
+    pub fn format_warning(&self, timeout: Duration) -> Option<String> {
+        self.wait_for_update(timeout)
+            .map(|update| self.render_notice(&update))
+    }
+
+    fn update_info(&self, release: Release) -> UpdateInfo {
+        let compared = self.compared_version();
+        UpdateInfo {
+            tool: self.tool_name.clone(),
+            current: self.current_version.clone(),
+            bump: self.version_policy.bump(&compared, &release.version),
+            versions_behind: self.version_policy.versions_behind(
+                &compared,
+                &release.version,
+                &release.versions,
+            ),
+            latest: release.version,
+            url: release.url,
+            published_at: release.published_at,
+            notes: release.notes,
+        }
+    }
+
+    /// Prints the notice if the background check found a newer version,
+    /// waiting up to 500ms for it.
+    ///
+    /// The notice is printed at most once per checker: later calls do
+    /// nothing until [`rearm`](Self::rearm) is called, and then print the
+    /// same result again without re-checking. [`warning_shown`](Self::warning_shown)
+    /// tells whether it was printed.
+    ///
+    /// # Clap integration
+    ///
+    /// If your CLI uses clap, you must use `Parser::try_parse()` instead of
+    /// `parse()`. clap's `parse()` calls `std::process::exit()` on `--help`
+    /// and `--version`, which will skip this call entirely. With `try_parse()`,
+    /// call `e.print()` first in the `Err` branch, then `print_warning()`, then
+    /// `std::process::exit(e.exit_code())`. See the README for a full example.
+    pub fn print_warning(&self) {
+        self.print_status(Duration::from_millis(500));
+    }
+
+    /// See [`print_warning`](Self::print_warning) for clap integration notes.
+    pub fn print_warning_sync(&self) {
+        self.print_status(Duration::from_secs(6));
+    }
+
+    /// Like [`print_warning`](Self::print_warning), but never waits: when
+    /// the background check hasn't finished, the notice comes from the last
+    /// cached answer instead, read on the calling thread. For tools that are
+    /// done in far less than the 500ms `print_warning` would wait.
+    pub fn print_warning_nonblocking(&self) {
+        if self.placement == Placement::NextRun {
+            return;
+        }
+        let status = self.wait_for_status(Duration::ZERO);
+        let in_flight = self
+            .state
+            .lock()
+            .is_ok_and(|state| state.receiver.is_some());
+        if in_flight {
+            self.print_cached_update();
+        } else {
+            self.print(status);
+        }
+    }
+
+    fn print_status(&self, timeout: Duration) {
+        if self.placement == Placement::NextRun {
+            return;
+        }
+        self.print(self.wait_for_status(timeout));
+    }
+
+    fn print(&self, status: Option<UpdateStatus>) {
+        match status {
+            Some(UpdateStatus::Available(ref update)) => self.print_update_message(update),
+            Some(UpdateStatus::AheadOfRegistry { ref latest, .. }) => {
+                self.print_ahead_message(latest)
+            }
+            Some(UpdateStatus::UpToDate) | None => self.print_stale_message(),
+        }
+    }
+
+    /// Prints [`Translation::stale`] when [`stale_after`](Self::stale_after) is
+    /// set and the running version has been running for that long.
+    fn print_stale_message(&self) {
+        let Some(stale_after) = self.stale_after else {
+            return;
+        };
+        if self.output == Output::Suppressed || checks_disabled() {
+            return;
+        }
+
+        // Decided from what the background check saw; the cache is only
+        // touched again when the notice is actually shown.
+        let now = get_current_timestamp();
+        let Some(info) = self.checked_info() else {
+            return;
+        };
+        let since = match (info.running.as_deref(), info.running_since) {
+            (Some(running), Some(since)) if running == self.compared_version() => since,
+            _ => return,
+        };
+        if info.snoozed_until.is_some_and(|until| until > now) {
+            return;
+        }
+        let age = now.saturating_sub(since);
+        let recently_shown = info
+            .stale_notice_shown
+            .is_some_and(|shown| now.saturating_sub(shown) < self.check_interval.as_secs());
+        if age < stale_after.as_secs() || recently_shown || self.shown.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        if let Some(mut cached) = load_entry(&self.tool_name) {
+            cached.stale_notice_shown = Some(now);
+            save_entry(&self.tool_name, &cached);
+        }
+        self.update_checked_info(|info| info.stale_notice_shown = Some(now));
+        let latest = info.latest;
+
+        let translation = locale::resolve(self.locale.as_deref(), &self.translations);
+        let days = age / 86400;
+        let template = translation.stale.replace("{days}", &days.to_string());
+        let message = self.render_message(
+            "stale",
+            translation.note.clone(),
+            &template,
+            &latest,
+            Some(days),
+        );
+        self.write_message(&message);
+    }
+
+    /// Prints the [`ahead_message`](Self::ahead_message), if one is set or
+    /// the running version is ahead of the [`blessed`](Self::blessed) one.
+    fn print_ahead_message(&self, latest: &str) {
+        let translation = locale::resolve(self.locale.as_deref(), &self.translations);
+        let template = match self.ahead_message {
+            Some(ref template) => template,
+            None if self.version_policy.pinned => &translation.ahead,
+            None => return,
+        };
+        if self.output == Output::Suppressed
+            || self.snoozed()
+            || self.shown.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        let message = self.render_message("ahead", String::new(), template, latest, None);
+        self.write_message(&message);
+    }
+
+    /// Renders a message about the running version rather than an update:
+    /// `template` filled and laid out after `prefix`, or a JSON line of type
+    /// `kind` with [`NoticeFormat::Json`].
+    fn render_message(
+        &self,
+        kind: &str,
+        prefix: String,
+        template: &str,
+        latest: &str,
+        days: Option<u64>,
+    ) -> String {
+        if self.notice_format != NoticeFormat::Json {
+            return self.layout(prefix, self.message_lines(template, latest));
+        }
+        let values = self.message_values(latest);
+        let command = message::render_template(self.resolve_update_command(), &values);
+        let line = message::JsonMessage {
+            kind,
+            tool: values.tool,
+            current: values.current,
+            latest,
+            command: &command,
+            days,
+        };
+        json::to_string(&line)
+            .map(|line| format!("{}\n", line))
+            .unwrap_or_default()
+    }
+
+    /// Fills `template`, a message about the running version rather than an
+    /// update, for [`layout`](Self::layout). `{latest}` is `latest`, in the
+    /// update command too.
+    fn message_lines(&self, template: &str, latest: &str) -> Vec<String> {
+        let mut values = self.message_values(latest);
+        let command = message::render_template(self.resolve_update_command(), &values);
+        values.command = &command;
+        message::render_template(template, &values)
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn message_values<'a>(&'a self, latest: &'a str) -> message::TemplateValues<'a> {
+        message::TemplateValues {
+            tool: &self.tool_name,
+            current: &self.current_version,
+            latest,
+            bump: "",
+            command: "",
+            url: "",
+            age: "",
+            behind: "",
+        }
+    }
+
+    /// Writes `message`, already laid out, to the configured output, or
+    /// leaves it to the user's shell if they asked for
+    /// [shell notices](Self::set_shell_notices).
+    fn write_message(&self, message: &str) {
+        if self.shell_notices() {
+            shell_hook::write_notice(&self.tool_name, message);
+            return;
+        }
+        // The whole message goes out in a single locked write so that output
+        // from the host's other threads can't land in the middle of it.
+        let mut write = || {
+            let _ = match self.output {
+                Output::Stderr => std::io::stderr().lock().write_all(message.as_bytes()),
+                Output::Stdout => {
+                    let mut stdout = std::io::stdout().lock();
+                    stdout
+                        .write_all(message.as_bytes())
+                        .and_then(|_| stdout.flush())
+                }
+                Output::Suppressed => Ok(()),
+            };
+        };
+        match self.suspend_output {
+            Some(ref suspend) if self.output != Output::Suppressed => suspend(&mut write),
+            _ => write(),
+        }
+    }
+
+    fn print_update_message(&self, update: &UpdateInfo) {
+        if self.snoozed() || self.shown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut notice = self.render_notice(update);
+        let plain = matches!(self.notice_format, NoticeFormat::Text | NoticeFormat::Boxed);
+        if plain && self.verbosity != Verbosity::Quiet && self.take_binstall_hint() {
+            notice.push_str(BINSTALL_HINT);
+        }
+
+        self.write_message(&notice);
+
+        if let Some(ref notifier) = self.notifier {
+            notifier.notify(update, &self.expanded_update_command(update));
+        }
+
+        if self.prompt_to_update && self.output != Output::Suppressed && !self.shell_notices() {
+            let command = self.expanded_update_command(update);
+            // `./mach bootstrap` only works from a Firefox checkout.
+            if InstallMethod::detect() != InstallMethod::MozBuild
+                && prompt::runs_installer(&command)
+            {
+                prompt::offer_update(&update.tool, &update.latest, &command);
+            }
+        }
+    }
+
+    fn expanded_update_command(&self, update: &UpdateInfo) -> String {
+        let translation = locale::resolve(self.locale.as_deref(), &self.translations);
+        let details = TemplateDetails::new(update, &translation);
+        let values = self.template_values(update, &details);
+        message::render_template(self.resolve_update_command(), &values)
+    }
+
+    fn template_values<'a>(
+        &self,
+        update: &'a UpdateInfo,
+        details: &'a TemplateDetails,
+    ) -> message::TemplateValues<'a> {
+        message::TemplateValues {
+            tool: &update.tool,
+            current: &update.current,
+            latest: &update.latest,
+            bump: update.bump.as_str(),
+            command: "",
+            url: &details.url,
+            age: &details.age,
+            behind: &details.behind,
+        }
+    }
+
+    fn render_notice(&self, update: &UpdateInfo) -> String {
+        let translation = locale::resolve(self.locale.as_deref(), &self.translations);
+        let details = TemplateDetails::new(update, &translation);
+        let mut values = self.template_values(update, &details);
+        let command = message::render_template(self.resolve_update_command(), &values);
+        values.command = &command;
+
+        if self.notice_format == NoticeFormat::Json {
+            let line = message::JsonNotice {
+                kind: "update-available",
+                tool: values.tool,
+                current: values.current,
+                latest: values.latest,
+                bump: values.bump,
+                command: values.command,
+                url: values.url,
+                published_at: update.published_at,
+                versions_behind: update.versions_behind,
+                dev_build: self.annotates_dev_build(),
+            };
+            return json::to_string(&line)
+                .map(|line| format!("{}\n", line))
+                .unwrap_or_default();
+        }
+
+        let is_terminal = match self.output {
+            Output::Stderr => std::io::stderr().is_terminal(),
+            Output::Stdout => std::io::stdout().is_terminal(),
+            Output::Suppressed => false,
+        };
+        let accessible = self.is_accessible();
+        let ansi = console::enable_ansi(self.output);
+        let hyperlinks = !accessible
+            && ansi
+            && !values.url.is_empty()
+            && message::supports_hyperlinks(is_terminal);
+
+        let (prefix, mut lines) = match self.message_template {
+            Some(ref template) => (
+                String::new(),
+                message::render_template(template, &values)
+                    .lines()
+                    .map(String::from)
+                    .collect(),
+            ),
+            None => {
+                let headline = match update.bump {
+                    Bump::Patch => &translation.patch_available,
+                    Bump::Minor => &translation.minor_available,
+                    Bump::Major => &translation.major_available,
+                };
+                let latest = if hyperlinks {
+                    message::hyperlink(values.latest, values.url)
+                } else {
+                    values.latest.to_string()
+                };
+                let linked_values = message::TemplateValues {
+                    latest: &latest,
+                    ..values
+                };
+
+                let render = |template: &str| message::render_template(template, &values);
+                let mut lines = Vec::new();
+                match self.resolve_verbosity() {
+                    Verbosity::Quiet => {
+                        lines.push(message::render_template(&translation.quiet, &linked_values));
+                    }
+                    Verbosity::Normal => {
+                        lines.push(message::render_template(headline, &linked_values));
+                        if !values.age.is_empty() {
+                            lines.push(render(&translation.released));
+                        }
+                        lines.push(render(&translation.run));
+                        if !hyperlinks && !accessible && !values.url.is_empty() {
+                            lines.push(render(&translation.release));
+                        }
+                    }
+                    Verbosity::Verbose => {
+                        lines.push(message::render_template(headline, &linked_values));
+                        lines.push(render(&translation.gap));
+                        if !values.age.is_empty() {
+                            lines.push(render(&translation.released));
+                        }
+                        lines.push(render(&translation.run));
+                        if !accessible && !values.url.is_empty() {
+                            lines.push(render(&translation.release));
+                        }
+                        if let Some(ref notes) = update.notes {
+                            lines.push(translation.notes.clone());
+                            lines.extend(notes.lines().map(|line| format!("  {}", line)));
+                        }
+                    }
+                }
+                (translation.note.clone(), lines)
+            }
+        };
+        if self.annotates_dev_build() {
+            lines.push(message::render_template(&translation.dev_build, &values));
+        }
+
+        self.layout(prefix, lines)
+    }
+
+    /// Whether the notice says that the running version is a development
+    /// build, with [`DevBuildPolicy::Annotate`].
+    fn annotates_dev_build(&self) -> bool {
+        self.dev_builds == DevBuildPolicy::Annotate
+            && version::dev_build_base(&self.current_version).is_some()
+    }
+
+    /// Lays out a notice whose first line starts with `prefix`: as a single
+    /// plain sentence for screen readers, boxed, or indented under `prefix`,
+    /// in ASCII if the terminal needs it.
+    fn layout(&self, prefix: String, lines: Vec<String>) -> String {
+        let accessible = self.is_accessible();
+        let ansi = console::enable_ansi(self.output);
+        // Templates may carry their own escape sequences; drop them where
+        // the console would print them raw.
+        let lines: Vec<String> = if ansi && !accessible {
+            lines
+        } else {
+            lines.iter().map(|line| console::strip_ansi(line)).collect()
+        };
+
+        if accessible {
+            return format!("{}\n", message::plain_sentence(&lines));
+        }
+
+        let ascii = match self.charset {
+            Charset::Auto => !message::locale_is_utf8(),
+            Charset::Unicode => false,
+            Charset::Ascii => true,
+        };
+        let (prefix, lines) = if ascii {
+            (
+                message::to_ascii(&prefix),
+                lines.iter().map(|line| message::to_ascii(line)).collect(),
+            )
+        } else {
+            (prefix, lines)
+        };
+
+        match self.notice_format {
+            NoticeFormat::Boxed => {
+                let mut lines = lines;
+                if let Some(first) = lines.first_mut() {
+                    first.insert_str(0, &prefix);
+                }
+                message::boxed(&lines, message::terminal_width(), ascii)
+            }
+            _ => {
+                let indent = " ".repeat(prefix.chars().count());
+                let mut notice = String::new();
+                for (i, line) in lines.iter().enumerate() {
+                    notice.push_str(if i == 0 { &prefix } else { &indent });
+                    notice.push_str(line);
+                    notice.push('\n');
+                }
+                notice
+            }
+        }
+    }
+
+    fn is_accessible(&self) -> bool {
+        self.accessible || std::env::var(ACCESSIBLE_ENV).is_ok_and(|value| value != "0")
+    }
+}
+
+#[cfg(not(all(feature = "browser", target_arch = "wasm32")))]
+pub(crate) fn get_current_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Browsers' wasm has no system clock, but JavaScript does.
+#[cfg(all(feature = "browser", target_arch = "wasm32"))]
+pub(crate) fn get_current_timestamp() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+fn get_check_interval_seconds() -> u64 {
+    std::env::var(UPDATE_CHECK_INTERVAL_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_CHECK_INTERVAL_SECONDS)
+}
+
+/// When the cached answer for `tool` was last fetched, if ever.
+#[cfg(feature = "clap")]
+pub(crate) fn last_checked(tool_name: &str) -> Option<u64> {
+    load_entry(tool_name).map(|info| info.last_check)
+}
+
+/// Asks `source` for the latest release of `tool_name` to offer users of
+/// `current_version` and caches it, whether or not it is newer. Returns
+/// `None` if the lookup failed.
+#[cfg(feature = "clap")]
+pub(crate) fn refresh_release(
+    source: &dyn Source,
+    tool_name: &str,
+    current_version: &str,
+    policy: &VersionPolicy,
+) -> Option<Release> {
+    let release = select_release(
+        source,
+        tool_name,
+        current_version,
+        policy,
+        source.latest_release(tool_name)?,
+    );
+    let previous = load_entry(tool_name).unwrap_or_default();
+    save_entry(
+        tool_name,
+        &previous.refreshed(get_current_timestamp(), &release),
+    );
+    Some(release)
+}
+
+/// Whether the detected install method suggests `cargo binstall`, and it is
+/// installed.
+fn binstall_suggested() -> bool {
+    let suggested = InstallMethod::detect()
+        .suggested_command()
+        .unwrap_or("cargo binstall {tool}");
+    suggested.starts_with("cargo binstall") && !binstall_missing(suggested)
+}
+
+fn binstall_missing(command: &str) -> bool {
+    command.starts_with("cargo binstall") && install::find_executable("cargo-binstall").is_none()
+}
+
+fn load_cache() -> VersionCache {
+    store::load()
+        .and_then(|content| json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Held while a cache entry is read and written back, so that entries saved
+/// by other threads meanwhile aren't lost.
+static SAVING: Mutex<()> = Mutex::new(());
+
+/// The cached entry of `tool_name`: from its own file if it has one, from
+/// the combined cache otherwise.
+fn load_entry(tool_name: &str) -> Option<ToolVersionInfo> {
+    let own = store::load_tool(tool_name).and_then(|content| json::from_str(&content).ok());
+    own.or_else(|| load_cache().tools.remove(tool_name))
+}
+
+/// Saves `info` as the entry of `tool_name`, in its own file with
+/// [`set_cache_per_tool`], or else over the combined cache as it is now
+/// rather than as it was when `info` was read, so that checks of other tools
+/// running meanwhile keep theirs.
+fn save_entry(tool_name: &str, info: &ToolVersionInfo) {
+    let _saving = SAVING.lock().unwrap_or_else(|e| e.into_inner());
+    write_entry(tool_name, info);
+}
+
+/// [`save_entry`], with [`SAVING`] already held.
+fn write_entry(tool_name: &str, info: &ToolVersionInfo) {
+    if store::per_tool() {
+        if let Ok(content) = json::to_file_string(info) {
+            store::save_tool(tool_name, &content);
+        }
+        return;
+    }
+    let mut cache = load_cache();
+    cache.tools.insert(tool_name.to_string(), info.clone());
+    if let Ok(content) = json::to_file_string(&cache) {
+        store::save(&content);
+    }
+}
+
+/// The newer version recorded in the cache for `tool_name`, without any
+/// network access.
+fn cached_update(
+    source: &dyn Source,
+    tool_name: &str,
+    current_version: &str,
+    policy: &VersionPolicy,
+    min_sightings: u32,
+) -> Option<Release> {
+    if let Some(checked) = fake_release(source, tool_name, current_version, policy) {
+        return checked.into_update();
+    }
+
+    let info = load_entry(tool_name)?;
+    (info.sightings >= min_sightings && policy.is_update(current_version, &info.latest))
+        .then(|| cached_release(source, tool_name, info))
+}
+
+/// `MOZTOOLS_FAKE_LATEST` replaces every lookup; `None` when it isn't set.
+fn fake_release(
+    source: &dyn Source,
+    tool_name: &str,
+    current_version: &str,
+    policy: &VersionPolicy,
+) -> Option<Checked> {
+    let fake = std::env::var("MOZTOOLS_FAKE_LATEST").ok()?;
+    Some(if policy.is_update(current_version, &fake) {
+        let url = source.release_url(tool_name, &fake);
+        let release = Release::new(fake);
+        Checked::Update(match url {
+            Some(url) => release.with_url(url),
+            None => release,
+        })
+    } else if policy.is_newer(&fake, current_version) {
+        Checked::Ahead { latest: fake }
+    } else {
+        Checked::Current
+    })
+}
+
+fn cached_release(source: &dyn Source, tool_name: &str, info: ToolVersionInfo) -> Release {
+    Release {
+        url: info
+            .url
+            .or_else(|| source.release_url(tool_name, &info.latest)),
+        version: info.latest,
+        published_at: info.published_at,
+        notes: info.notes,
+        assets: Vec::new(),
+        versions: info.versions,
+    }
+}
+
+impl ToolVersionInfo {
+    /// What to cache about `release`, looked up at `now`, keeping what is
+    /// tracked across lookups.
+    fn refreshed(&self, now: u64, release: &Release) -> Self {
+        ToolVersionInfo {
+            last_check: now,
+            latest: release.version.clone(),
+            url: release.url.clone(),
+            published_at: release.published_at,
+            notes: release.notes.clone(),
+            versions: release.versions.clone(),
+            sightings: if release.version == self.latest {
+                self.sightings.saturating_add(1)
+            } else {
+                1
+            },
+            binstall_hint_shown: self.binstall_hint_shown,
+            running: self.running.clone(),
+            running_since: self.running_since,
+            stale_notice_shown: self.stale_notice_shown,
+            helper_started: self.helper_started,
+            snoozed_until: self.snoozed_until,
+            ahead: None,
+            prebuilt: None,
+        }
+    }
+}
+
+/// `release`, or the newest version it lists when that is the better one to
+/// offer users of `current_version`: the source reported a pre-release they
+/// didn't opt into, or they did and a newer pre-release is listed. A newer
+/// stable version is left alone, as the source may purposely not report the
+/// highest one.
+fn select_release(
+    source: &dyn Source,
+    tool_name: &str,
+    current_version: &str,
+    policy: &VersionPolicy,
+    release: Release,
+) -> Release {
+    let latest = match policy.latest(current_version, &release.versions) {
+        Some(latest) if latest != release.version => latest.to_string(),
+        _ => return release,
+    };
+    if policy.offers(current_version, &release.version)
+        && !(policy.is_prerelease(&latest) && policy.is_newer(&release.version, &latest))
+    {
+        return release;
+    }
+    Release {
+        url: source.release_url(tool_name, &latest),
+        version: latest,
+        published_at: None,
+        notes: None,
+        assets: Vec::new(),
+        versions: release.versions,
+    }
+}
+
+/// Everything a check needs, so it can run on the caller's thread or be
+/// moved to a background one.
+#[derive(Clone)]
+struct CheckRequest {
+    source: Arc<dyn Source>,
+    tool_name: String,
+    current_version: String,
+    check_interval: Duration,
+    policy: VersionPolicy,
+    probe_prebuilt: bool,
+    min_sightings: u32,
+    use_helper: bool,
+    timings: Timings,
+}
+
+/// What a check spent its time on so far.
+#[derive(Clone, Default)]
+struct Timings {
+    cache_read: Cell<Duration>,
+    fetch: Cell<Option<Duration>>,
+}
+
+/// Starts timing something, if there is a clock: browsers give wasm none.
+fn start_timer() -> Option<Instant> {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    return Some(Instant::now());
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    return None;
+}
+
+fn elapsed(started: Option<Instant>) -> Duration {
+    started.map(|started| started.elapsed()).unwrap_or_default()
+}
+
+impl CheckRequest {
+    /// Applies the user's configuration, which is read here rather than when
+    /// the checker is built, so that reading it is left to the thread.
+    fn honoring_config(mut self) -> Self {
+        self.policy.requirement = config::pin(&self.tool_name);
+        self
+    }
+
+    /// The outcome of the check if it needs no lookup: the cache is recent
+    /// enough and agrees about the running version, or `MOZTOOLS_FAKE_LATEST`
+    /// is set. Reads the cache but never writes it.
+    fn without_lookup(&self) -> Option<Checked> {
+        if let Some(checked) = self.fake_release() {
+            return Some(checked);
+        }
+        let info = self.load_entry()?;
+        if info.running.as_deref() != Some(self.current_version.as_str()) {
+            return None;
+        }
+        self.cached(&info, get_current_timestamp())
+    }
+
+    /// Runs the check the way `check_async` does: without writing the cache
+    /// when it needs no lookup.
+    fn in_background(&self) -> Outcome {
+        let started = start_timer();
+        let outcome = if self.use_helper {
+            self.via_helper()
+        } else {
+            Outcome {
+                checked: self.without_lookup().or_else(|| self.run()),
+                info: self.load_entry(),
+                ..Outcome::default()
+            }
+        };
+        self.finished(outcome, started)
+    }
+
+    /// The outcome `in_background` would send, if it is found without a
+    /// lookup nor a helper, so that no thread is needed to wait for it.
+    fn without_thread(&self) -> Option<Outcome> {
+        if self.use_helper {
+            return None;
+        }
+        let started = start_timer();
+        let outcome = Outcome {
+            checked: Some(self.without_lookup()?),
+            info: self.load_entry(),
+            ..Outcome::default()
+        };
+        Some(self.finished(outcome, started))
+    }
+
+    /// Completes `outcome` with the shell hook's state and the timings.
+    fn finished(&self, mut outcome: Outcome, started: Option<Instant>) -> Outcome {
+        outcome.shell_notices = shell_hook::enabled(&self.tool_name);
+        if outcome.shell_notices && !matches!(outcome.checked, Some(Checked::Update(_))) {
+            shell_hook::clear_notice(&self.tool_name);
+        }
+        outcome.report = UpdateCheckReport {
+            cache_read: self.timings.cache_read.get(),
+            fetch: self.timings.fetch.get(),
+            total: elapsed(started),
+        };
+        outcome
+    }
+
+    /// What the cache says however old it is, starting the helper process
+    /// when neither a lookup nor a helper has been seen for a check interval.
+    fn via_helper(&self) -> Outcome {
+        if let Some(checked) = self.fake_release() {
+            return Outcome {
+                checked: Some(checked),
+                ..Outcome::default()
+            };
+        }
+        let mut entry = self.load_entry().unwrap_or_default();
+        let now = get_current_timestamp();
+        let last_seen = entry.last_check.max(entry.helper_started.unwrap_or(0));
+        if now.saturating_sub(last_seen) >= self.check_interval.as_secs() {
+            // Recorded first so concurrent runs don't start one each.
+            entry.helper_started = Some(now);
+            save_entry(&self.tool_name, &entry);
+            helper::spawn(&self.tool_name);
+        }
+        let request = CheckRequest {
+            check_interval: Duration::MAX,
+            ..self.clone()
+        };
+        Outcome {
+            checked: request.cached(&entry, now),
+            info: Some(entry),
+            ..Outcome::default()
+        }
+    }
+
+    fn load_entry(&self) -> Option<ToolVersionInfo> {
+        let reading = start_timer();
+        let entry = load_entry(&self.tool_name);
+        let total = self.timings.cache_read.get() + elapsed(reading);
+        self.timings.cache_read.set(total);
+        entry
+    }
+
+    fn fake_release(&self) -> Option<Checked> {
+        fake_release(
+            &*self.source,
+            &self.tool_name,
+            &self.current_version,
+            &self.policy,
+        )
+    }
+
+    /// What the cache says, if it was refreshed within the check interval
+    /// and can be trusted.
+    fn cached(&self, info: &ToolVersionInfo, now: u64) -> Option<Checked> {
+        let (policy, current_version) = (&self.policy, self.current_version.as_str());
+        if now.saturating_sub(info.last_check) >= self.check_interval.as_secs() {
+            return None;
+        }
+        if policy.is_update(current_version, &info.latest) {
+            if info.sightings < self.min_sightings {
+                return Some(Checked::Current);
+            }
+            return Some(Checked::Update(cached_release(
+                &*self.source,
+                &self.tool_name,
+                info.clone(),
+            )));
+        }
+        // Running a version newer than the cached latest usually means the
+        // cache predates its release, so only trust it once a lookup has been
+        // attempted while running this version.
+        let ahead = !info.latest.is_empty() && policy.is_newer(&info.latest, current_version);
+        if !ahead {
+            return Some(Checked::Current);
+        }
+        (info.ahead.as_deref() == Some(current_version)).then(|| Checked::Ahead {
+            latest: info.latest.clone(),
+        })
+    }
+
+    /// Runs the check, looking the latest release up unless the cache is
+    /// recent enough.
+    fn run(&self) -> Option<Checked> {
+        if let Some(checked) = self.fake_release() {
+            return Some(checked);
+        }
+        let (source, tool_name) = (&*self.source, self.tool_name.as_str());
+        let (policy, current_version) = (&self.policy, self.current_version.as_str());
+
+        let mut previous = self.load_entry().unwrap_or_default();
+        let now = get_current_timestamp();
+
+        // Note when this version started running, to tell how old it gets.
+        if previous.running.as_deref() != Some(current_version) {
+            previous.running = Some(current_version.to_string());
+            previous.running_since = Some(now);
+            previous.stale_notice_shown = None;
+            save_entry(tool_name, &previous);
+        }
+
+        if let Some(checked) = self.cached(&previous, now) {
+            return Some(checked);
+        }
+
+        // Record the attempt right away, keeping what the last successful
+        // lookup found.
+        let ahead = (!previous.latest.is_empty()
+            && policy.is_newer(&previous.latest, current_version))
+        .then(|| current_version.to_string());
+        let attempt = ToolVersionInfo {
+            last_check: now,
+            ahead,
+            ..previous
+        };
+        save_entry(tool_name, &attempt);
+
+        let fetching = start_timer();
+        let release = source.latest_release(tool_name);
+        self.timings.fetch.set(Some(elapsed(fetching)));
+        let release = match release {
+            Some(release) => release,
+            None => {
+                if policy.is_update(current_version, &attempt.latest) {
+                    if attempt.sightings < self.min_sightings {
+                        return Some(Checked::Current);
+                    }
+                    return Some(Checked::Update(cached_release(source, tool_name, attempt)));
+                }
+                return attempt.ahead.map(|_| Checked::Ahead {
+                    latest: attempt.latest,
+                });
+            }
+        };
+
+        let release = select_release(source, tool_name, current_version, policy, release);
+        let ahead = policy.is_newer(&release.version, current_version);
+        let refreshed = attempt.refreshed(now, &release);
+        let sightings = refreshed.sightings;
+        let update = policy.is_update(current_version, &release.version);
+        let prebuilt = if !self.probe_prebuilt || !update || !binstall_suggested() {
+            None
+        } else if release.version == attempt.latest && attempt.prebuilt.is_some() {
+            attempt.prebuilt
+        } else {
+            install::prebuilt_available(tool_name, &release)
+        };
+        save_entry(
+            tool_name,
+            &ToolVersionInfo {
+                ahead: ahead.then(|| current_version.to_string()),
+                prebuilt,
+                ..refreshed
+            },
+        );
+
+        Some(if update && sightings >= self.min_sightings {
+            Checked::Update(release)
+        } else if ahead {
+            Checked::Ahead {
+                latest: release.version,
+            }
+        } else {
+            Checked::Current
+        })
     }
 }
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+/// The strings making up the prose notice for one locale.
+///
+/// Every string except `note` is a template using the same placeholders as
+/// [`VersionChecker::message_template`](crate::VersionChecker::message_template).
+/// `note` is the prefix of the first line, including its separator; the
+/// following lines are indented to line up with the text after it.
+///
+/// The `age_*` strings spell out how long ago the release was published and
+/// are what `{age}` expands to; `{n}` in them is replaced by the count.
+///
+/// New strings may be added in any release, so a translation is made by
+/// changing the fields of [`from_english`](Self::from_english) or of a
+/// [`builtin`](Self::builtin) one: strings it doesn't set stay in English.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Translation {
+    pub note: String,
+    pub patch_available: String,
+    pub minor_available: String,
+    pub major_available: String,
+    pub released: String,
+    pub run: String,
+    pub release: String,
+    /// The whole notice on one line, for [`Verbosity::Quiet`](crate::Verbosity::Quiet).
+    pub quiet: String,
+    /// Shown with [`Verbosity::Verbose`](crate::Verbosity::Verbose).
+    pub gap: String,
+    /// Heading of the release notes excerpt, with
+    /// [`Verbosity::Verbose`](crate::Verbosity::Verbose).
+    pub notes: String,
+    /// Shown when the running version is newer than the
+    /// [`blessed`](crate::VersionChecker::blessed) one, which `{latest}` is.
+    pub ahead: String,
+    /// Shown with [`stale_after`](crate::VersionChecker::stale_after), after
+    /// `note`, with `{days}` replaced by how long `{current}` has been used.
+    pub stale: String,
+    /// Added with [`DevBuildPolicy::Annotate`](crate::DevBuildPolicy::Annotate)
+    /// when `{current}` is a development build.
+    pub dev_build: String,
+    pub age_today: String,
+    pub age_yesterday: String,
+    pub age_days: String,
+    pub age_month: String,
+    pub age_months: String,
+    pub age_year: String,
+    pub age_years: String,
+}
+
+impl Translation {
+    /// The English strings, to start a translation from.
+    pub fn from_english() -> Self {
+        Self::builtin("en").expect("English translation is built in")
+    }
+
+    /// The translation shipped with this crate for `locale` (e.g. `fr` or
+    /// `fr_CA`), if there is one.
+    pub fn builtin(locale: &str) -> Option<Self> {
+        let strings = match language(locale) {
+            "en" => [
+                "Note: ",
+                "A bugfix release of {tool} is available (current: {current}, latest: {latest})",
+                "A newer version of {tool} is available (current: {current}, latest: {latest})",
+                "A major new version of {tool} is available (current: {current}, latest: {latest}); see the release notes before upgrading",
+                "Released {age}.",
+                "Run: {command}",
+                "Release: {url}",
+                "{tool} {latest} is available (current: {current}); run: {command}",
+                "Update: {current} → {latest} ({bump})",
+                "Release notes:",
+                "{tool} {current} is newer than {latest}, the version in use here. Run: {command}",
+                "{tool} {current} has been in use for {days} days and may be out of date. Run: {command}",
+                "({tool} {current} is a development build.)",
+                "today",
+                "yesterday",
+                "{n} days ago",
+                "1 month ago",
+                "{n} months ago",
+                "1 year ago",
+                "{n} years ago",
+            ],
+            "fr" => [
+                "Remarque : ",
+                "Une version corrective de {tool} est disponible (actuelle : {current}, dernière : {latest})",
+                "Une nouvelle version de {tool} est disponible (actuelle : {current}, dernière : {latest})",
+                "Une nouvelle version majeure de {tool} est disponible (actuelle : {current}, dernière : {latest}) ; consultez les notes de version avant de mettre à jour",
+                "Publiée {age}.",
+                "Exécutez : {command}",
+                "Notes de version : {url}",
+                "{tool} {latest} est disponible (actuelle : {current}) ; exécutez : {command}",
+                "Mise à jour : {current} → {latest} ({bump})",
+                "Notes de version :",
+                "{tool} {current} est plus récente que {latest}, la version utilisée ici. Exécutez : {command}",
+                "{tool} {current} est utilisée depuis {days} jours et n'est peut-être plus à jour. Exécutez : {command}",
+                "({tool} {current} est une version de développement.)",
+                "aujourd'hui",
+                "hier",
+                "il y a {n} jours",
+                "il y a 1 mois",
+                "il y a {n} mois",
+                "il y a 1 an",
+                "il y a {n} ans",
+            ],
+            "de" => [
+                "Hinweis: ",
+                "Eine Fehlerbehebungsversion von {tool} ist verfügbar (installiert: {current}, neueste: {latest})",
+                "Eine neuere Version von {tool} ist verfügbar (installiert: {current}, neueste: {latest})",
+                "Eine neue Hauptversion von {tool} ist verfügbar (installiert: {current}, neueste: {latest}); bitte vor dem Update die Versionshinweise lesen",
+                "Veröffentlicht {age}.",
+                "Ausführen: {command}",
+                "Versionshinweise: {url}",
+                "{tool} {latest} ist verfügbar (installiert: {current}); ausführen: {command}",
+                "Update: {current} → {latest} ({bump})",
+                "Versionshinweise:",
+                "{tool} {current} ist neuer als {latest}, die hier verwendete Version. Ausführen: {command}",
+                "{tool} {current} wird seit {days} Tagen verwendet und ist möglicherweise veraltet. Ausführen: {command}",
+                "({tool} {current} ist ein Entwicklungsbuild.)",
+                "heute",
+                "gestern",
+                "vor {n} Tagen",
+                "vor 1 Monat",
+                "vor {n} Monaten",
+                "vor 1 Jahr",
+                "vor {n} Jahren",
+            ],
+            "es" => [
+                "Nota: ",
+                "Hay una versión correctiva de {tool} disponible (actual: {current}, última: {latest})",
+                "Hay una versión más reciente de {tool} disponible (actual: {current}, última: {latest})",
+                "Hay una nueva versión mayor de {tool} disponible (actual: {current}, última: {latest}); consulta las notas de la versión antes de actualizar",
+                "Publicada {age}.",
+                "Ejecuta: {command}",
+                "Notas de la versión: {url}",
+                "{tool} {latest} está disponible (actual: {current}); ejecuta: {command}",
+                "Actualización: {current} → {latest} ({bump})",
+                "Notas de la versión:",
+                "{tool} {current} es más reciente que {latest}, la versión usada aquí. Ejecuta: {command}",
+                "{tool} {current} lleva {days} días en uso y puede estar desactualizada. Ejecuta: {command}",
+                "({tool} {current} es una compilación de desarrollo.)",
+                "hoy",
+                "ayer",
+                "hace {n} días",
+                "hace 1 mes",
+                "hace {n} meses",
+                "hace 1 año",
+                "hace {n} años",
+            ],
+            _ => return None,
+        };
+        Some(Translation::from_strings(strings))
+    }
+
+    fn from_strings(strings: [&str; 20]) -> Self {
+        let [note, patch_available, minor_available, major_available, released, run, release, quiet, gap, notes, ahead, stale, dev_build, age_today, age_yesterday, age_days, age_month, age_months, age_year, age_years] =
+            strings.map(str::to_string);
+        Self {
+            note,
+            patch_available,
+            minor_available,
+            major_available,
+            released,
+            run,
+            release,
+            quiet,
+            gap,
+            notes,
+            ahead,
+            stale,
+            dev_build,
+            age_today,
+            age_yesterday,
+            age_days,
+            age_month,
+            age_months,
+            age_year,
+            age_years,
+        }
+    }
+
+    /// Spells out an age of `seconds`, e.g. `3 days ago`.
+    pub(crate) fn age(&self, seconds: u64) -> String {
+        let days = seconds / 86400;
+        let (template, n) = match days {
+            0 => (&self.age_today, 0),
+            1 => (&self.age_yesterday, 1),
+            2..=29 => (&self.age_days, days),
+            30..=59 => (&self.age_month, 1),
+            60..=364 => (&self.age_months, days / 30),
+            365..=729 => (&self.age_year, 1),
+            _ => (&self.age_years, days / 365),
+        };
+        template.replace("{n}", &n.to_string())
+    }
+}
+
+/// Picks the translation for `explicit`, or for the locale from the
+/// environment, preferring translations registered by the host. Each locale
+/// is tried as given (`fr_CA`) and then by language (`fr`), falling back to
+/// English.
+pub(crate) fn resolve(
+    explicit: Option<&str>,
+    registered: &HashMap<String, Translation>,
+) -> Translation {
+    let locale = explicit
+        .map(str::to_string)
+        .or_else(locale_from_env)
+        .unwrap_or_default();
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+
+    // Built-in translations match any region of their language, so the
+    // registered ones are all tried first: a host's `fr` wins over the
+    // built-in French for `fr_CA`.
+    let candidates = [locale, language(locale)];
+    candidates
+        .iter()
+        .find_map(|candidate| registered.get(*candidate).cloned())
+        .or_else(|| {
+            candidates
+                .iter()
+                .find_map(|candidate| Translation::builtin(candidate))
+        })
+        .unwrap_or_else(Translation::from_english)
+}
+
+fn language(locale: &str) -> &str {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+}
+
+fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| value != "C" && value != "POSIX")
+}
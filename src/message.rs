@@ -0,0 +1,337 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub(crate) struct JsonNotice<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'a str,
+    pub tool: &'a str,
+    pub current: &'a str,
+    pub latest: &'a str,
+    pub bump: &'a str,
+    pub command: &'a str,
+    pub url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub versions_behind: Option<usize>,
+    /// Set with [`DevBuildPolicy::Annotate`](crate::DevBuildPolicy::Annotate)
+    /// when the running version is a development build.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dev_build: bool,
+}
+
+#[cfg(feature = "tiny")]
+impl crate::json::ToJson for JsonNotice<'_> {
+    fn to_json(&self) -> serde_json::Value {
+        let mut fields = serde_json::Map::new();
+        for (key, value) in [
+            ("type", self.kind),
+            ("tool", self.tool),
+            ("current", self.current),
+            ("latest", self.latest),
+            ("bump", self.bump),
+            ("command", self.command),
+            ("url", self.url),
+        ] {
+            fields.insert(key.to_string(), value.into());
+        }
+        if let Some(published_at) = self.published_at {
+            fields.insert("published_at".to_string(), published_at.into());
+        }
+        if let Some(versions_behind) = self.versions_behind {
+            fields.insert("versions_behind".to_string(), versions_behind.into());
+        }
+        if self.dev_build {
+            fields.insert("dev_build".to_string(), true.into());
+        }
+        serde_json::Value::Object(fields)
+    }
+}
+
+/// A notice about the running version rather than an update, in the shape
+/// of [`JsonNotice`].
+#[derive(Serialize)]
+pub(crate) struct JsonMessage<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'a str,
+    pub tool: &'a str,
+    pub current: &'a str,
+    pub latest: &'a str,
+    pub command: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<u64>,
+}
+
+#[cfg(feature = "tiny")]
+impl crate::json::ToJson for JsonMessage<'_> {
+    fn to_json(&self) -> serde_json::Value {
+        let mut fields = serde_json::Map::new();
+        for (key, value) in [
+            ("type", self.kind),
+            ("tool", self.tool),
+            ("current", self.current),
+            ("latest", self.latest),
+            ("command", self.command),
+        ] {
+            fields.insert(key.to_string(), value.into());
+        }
+        if let Some(days) = self.days {
+            fields.insert("days".to_string(), days.into());
+        }
+        serde_json::Value::Object(fields)
+    }
+}
+
+pub(crate) struct TemplateValues<'a> {
+    pub tool: &'a str,
+    pub current: &'a str,
+    pub latest: &'a str,
+    pub bump: &'a str,
+    pub command: &'a str,
+    pub url: &'a str,
+    pub age: &'a str,
+    pub behind: &'a str,
+}
+
+impl TemplateValues<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "tool" => Some(self.tool),
+            "current" => Some(self.current),
+            "latest" => Some(self.latest),
+            "bump" => Some(self.bump),
+            "command" => Some(self.command),
+            "url" => Some(self.url),
+            "age" => Some(self.age),
+            "behind" => Some(self.behind),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn render_template(template: &str, values: &TemplateValues) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => match values.get(&after[..end]) {
+                Some(value) => {
+                    out.push_str(value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = after;
+                }
+            },
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Wraps `text` in an OSC 8 escape sequence so terminals render it as a link.
+pub(crate) fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Best-effort detection of OSC 8 support for a stream that is a terminal.
+///
+/// `FORCE_HYPERLINK=1`/`0` overrides the detection, as in other tools that
+/// emit hyperlinks.
+pub(crate) fn supports_hyperlinks(is_terminal: bool) -> bool {
+    if let Ok(value) = std::env::var("FORCE_HYPERLINK") {
+        return value != "0";
+    }
+
+    if !is_terminal || std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+        return false;
+    }
+
+    if let Ok(program) = std::env::var("TERM_PROGRAM") {
+        if matches!(
+            program.as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+        ) {
+            return true;
+        }
+    }
+
+    if std::env::var("VTE_VERSION")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .is_some_and(|v| v >= 5000)
+    {
+        return true;
+    }
+
+    [
+        "WT_SESSION",
+        "KONSOLE_VERSION",
+        "KITTY_WINDOW_ID",
+        "DOMTERM",
+    ]
+    .iter()
+    .any(|var| std::env::var_os(var).is_some())
+}
+
+/// Joins the lines of a notice into one sentence, e.g. `A newer version of
+/// foo is available (current: 1.0.0, latest: 1.1.0). Run: cargo binstall foo.`
+pub(crate) fn plain_sentence(lines: &[String]) -> String {
+    let mut sentence = lines
+        .iter()
+        .map(|line| line.trim().trim_end_matches(['.', ';', ':']))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(". ");
+    if !sentence.is_empty() {
+        sentence.push('.');
+    }
+    sentence
+}
+
+/// The width available for the notice, from `COLUMNS`, defaulting to 80.
+pub(crate) fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|width| *width >= 20)
+        .unwrap_or(80)
+}
+
+/// Number of columns `text` occupies, ignoring OSC 8 escape sequences.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find("\x1b]8;") {
+        width += rest[..start].chars().count();
+        rest = match rest[start..].find("\x1b\\") {
+            Some(end) => &rest[start + end + 2..],
+            None => "",
+        };
+    }
+    width + rest.chars().count()
+}
+
+/// Greedily wraps `line` on spaces so no row exceeds `width` columns, unless
+/// a single word is wider than that.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut row_width = 0;
+
+    for word in line.split(' ') {
+        let word_width = visible_width(word);
+        if row_width > 0 && row_width + 1 + word_width > width {
+            rows.push(std::mem::take(&mut row));
+            row_width = 0;
+        }
+        if row_width > 0 {
+            row.push(' ');
+            row_width += 1;
+        }
+        row.push_str(word);
+        row_width += word_width;
+    }
+
+    rows.push(row);
+    rows
+}
+
+/// Draws `lines` inside a box no wider than `max_width` columns, using
+/// box-drawing characters or, with `ascii`, plain `+`, `-` and `|`.
+pub(crate) fn boxed(lines: &[String], max_width: usize, ascii: bool) -> String {
+    let inner = lines
+        .iter()
+        .map(|line| visible_width(line))
+        .max()
+        .unwrap_or(0)
+        .min(max_width.saturating_sub(4));
+
+    let rows: Vec<String> = lines.iter().flat_map(|line| wrap(line, inner)).collect();
+    let inner = rows
+        .iter()
+        .map(|row| visible_width(row))
+        .max()
+        .unwrap_or(0)
+        .max(inner);
+
+    let (top_left, top_right, bottom_left, bottom_right, horizontal, vertical) = if ascii {
+        ("+", "+", "+", "+", "-", "|")
+    } else {
+        ("╭", "╮", "╰", "╯", "─", "│")
+    };
+
+    let horizontal = horizontal.repeat(inner + 2);
+    let mut out = format!("{}{}{}\n", top_left, horizontal, top_right);
+    for row in &rows {
+        let padding = " ".repeat(inner - visible_width(row));
+        out.push_str(&format!("{} {}{} {}\n", vertical, row, padding, vertical));
+    }
+    out.push_str(&format!("{}{}{}\n", bottom_left, horizontal, bottom_right));
+    out
+}
+
+/// Whether the locale in the environment says the terminal expects UTF-8.
+pub(crate) fn locale_is_utf8() -> bool {
+    if cfg!(windows) {
+        return std::env::var_os("WT_SESSION").is_some();
+    }
+
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+        .unwrap_or(false)
+}
+
+/// Folds `text` to ASCII: accented Latin letters lose their accents, common
+/// punctuation gets an ASCII spelling, and anything else becomes `?`.
+pub(crate) fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii() {
+            out.push(c);
+            continue;
+        }
+        let folded = match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => "a",
+            'À' | 'Á' | 'Â' | 'Ä' | 'Ã' | 'Å' => "A",
+            'ç' => "c",
+            'Ç' => "C",
+            'è' | 'é' | 'ê' | 'ë' => "e",
+            'È' | 'É' | 'Ê' | 'Ë' => "E",
+            'ì' | 'í' | 'î' | 'ï' => "i",
+            'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+            'ñ' => "n",
+            'Ñ' => "N",
+            'ò' | 'ó' | 'ô' | 'ö' | 'õ' => "o",
+            'Ò' | 'Ó' | 'Ô' | 'Ö' | 'Õ' => "O",
+            'ù' | 'ú' | 'û' | 'ü' => "u",
+            'Ù' | 'Ú' | 'Û' | 'Ü' => "U",
+            'ß' => "ss",
+            '→' | '⟶' => "->",
+            '←' => "<-",
+            '–' | '—' | '─' => "-",
+            '‘' | '’' => "'",
+            '“' | '”' | '«' | '»' => "\"",
+            '…' => "...",
+            '\u{a0}' | '\u{202f}' => " ",
+            _ => "?",
+        };
+        out.push_str(folded);
+    }
+    out
+}
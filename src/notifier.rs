@@ -0,0 +1,31 @@
+use crate::UpdateInfo;
+
+/// Delivers update notices somewhere other than the terminal.
+///
+/// A notifier is called in addition to the stream selected with
+/// [`VersionChecker::output`](crate::VersionChecker::output); combine it with
+/// [`Output::Suppressed`](crate::Output::Suppressed) to only use the notifier.
+pub trait Notifier: Send + Sync {
+    /// `update_command` is the command suggested to the user, with its
+    /// placeholders already expanded.
+    fn notify(&self, update: &UpdateInfo, update_command: &str);
+}
+
+/// Shows the notice as a native desktop notification.
+#[cfg(feature = "desktop-notification")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DesktopNotifier;
+
+#[cfg(feature = "desktop-notification")]
+impl Notifier for DesktopNotifier {
+    fn notify(&self, update: &UpdateInfo, update_command: &str) {
+        let _ = notify_rust::Notification::new()
+            .appname(&update.tool)
+            .summary(&format!("{} {} is available", update.tool, update.latest))
+            .body(&format!(
+                "You are running {}. Update with: {}",
+                update.current, update_command
+            ))
+            .show();
+    }
+}
@@ -0,0 +1,54 @@
+/// Platforms that can be given their own update command.
+///
+/// `Linux` matches any Linux build; `LinuxGnu` and `LinuxMusl` only match
+/// binaries built against that libc, and take precedence over `Linux`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Linux,
+    LinuxGnu,
+    LinuxMusl,
+    MacOs,
+    Windows,
+}
+
+impl Platform {
+    /// The most specific platform this binary was compiled for, if it is one
+    /// we know about.
+    pub fn current() -> Option<Self> {
+        match std::env::consts::OS {
+            "linux" if cfg!(target_env = "musl") => Some(Platform::LinuxMusl),
+            "linux" if cfg!(target_env = "gnu") => Some(Platform::LinuxGnu),
+            "linux" => Some(Platform::Linux),
+            "macos" => Some(Platform::MacOs),
+            "windows" => Some(Platform::Windows),
+            _ => None,
+        }
+    }
+
+    /// This platform followed by the less specific ones it belongs to, in
+    /// lookup order.
+    pub(crate) fn lookup_order(self) -> &'static [Platform] {
+        match self {
+            Platform::LinuxGnu => &[Platform::LinuxGnu, Platform::Linux],
+            Platform::LinuxMusl => &[Platform::LinuxMusl, Platform::Linux],
+            Platform::Linux => &[Platform::Linux],
+            Platform::MacOs => &[Platform::MacOs],
+            Platform::Windows => &[Platform::Windows],
+        }
+    }
+}
+
+/// The Rust target triple this binary was built for, e.g.
+/// `x86_64-unknown-linux-gnu`, as used in release asset names.
+pub fn target_triple() -> String {
+    let arch = std::env::consts::ARCH;
+    match std::env::consts::OS {
+        "linux" if cfg!(target_env = "musl") => format!("{}-unknown-linux-musl", arch),
+        "linux" => format!("{}-unknown-linux-gnu", arch),
+        "macos" => format!("{}-apple-darwin", arch),
+        "windows" if cfg!(target_env = "gnu") => format!("{}-pc-windows-gnu", arch),
+        "windows" => format!("{}-pc-windows-msvc", arch),
+        "freebsd" => format!("{}-unknown-freebsd", arch),
+        os => format!("{}-unknown-{}", arch, os),
+    }
+}
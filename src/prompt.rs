@@ -0,0 +1,60 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::Command;
+
+/// The installers whose commands the prompt offers to run. Anything else,
+/// such as `./mach bootstrap`, is only a hint for the user to follow.
+const INSTALLERS: &[&str] = &["cargo", "cargo-binstall", "brew", "scoop", "winget"];
+
+/// Whether `command` runs one of the [`INSTALLERS`].
+pub(crate) fn runs_installer(command: &str) -> bool {
+    command.split_whitespace().next().is_some_and(|program| {
+        let program = program.strip_suffix(".exe").unwrap_or(program);
+        INSTALLERS.contains(&program)
+    })
+}
+
+/// Asks whether to run `command` now and runs it on confirmation, with its
+/// output going straight to the terminal. Does nothing unless both stdin and
+/// stderr are terminals.
+pub(crate) fn offer_update(tool: &str, latest: &str, command: &str) {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return;
+    }
+
+    {
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "Update now? [y/N] ");
+        let _ = stderr.flush();
+    }
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return;
+    }
+    if !matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+        return;
+    }
+
+    match shell_command(command).status() {
+        Ok(status) if status.success() => {
+            eprintln!(
+                "{} was updated to {}; re-run it to use the new version.",
+                tool, latest
+            )
+        }
+        Ok(status) => eprintln!("`{}` failed ({}).", command, status),
+        Err(err) => eprintln!("Could not run `{}`: {}", command, err),
+    }
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
@@ -0,0 +1,162 @@
+use super::download::ArchiveKind;
+use crate::Asset;
+
+/// Names each OS goes by in release asset names.
+const OS_ALIASES: &[(&str, &[&str])] = &[
+    ("linux", &["linux"]),
+    ("macos", &["darwin", "macos", "apple", "osx", "mac"]),
+    ("windows", &["windows", "win", "win32", "win64", "msvc"]),
+    ("freebsd", &["freebsd"]),
+];
+
+/// Names each architecture goes by. `x86_64` is rewritten to `amd64` before
+/// matching, as it would otherwise be split on the underscore.
+const ARCH_ALIASES: &[(&str, &[&str])] = &[
+    ("x86_64", &["amd64", "x64", "win64"]),
+    ("aarch64", &["aarch64", "arm64"]),
+    ("x86", &["i686", "i586", "i386", "386", "x86", "win32"]),
+    ("arm", &["armv7", "armv7l", "armhf", "arm"]),
+];
+
+/// Picks the release asset to install on this machine.
+///
+/// With `patterns`, the first asset matching the first pattern that matches
+/// anything is used. Otherwise assets are matched on the OS, architecture and
+/// libc in their name, preferring an exact target triple, then the host's
+/// libc (a static musl build is accepted on glibc but not the other way
+/// round), then the archive format native to the platform.
+pub(crate) fn select_asset<'a>(
+    assets: &'a [Asset],
+    tool: &str,
+    version: &str,
+    patterns: &[String],
+) -> Option<&'a Asset> {
+    let installable = || {
+        assets
+            .iter()
+            .filter(|asset| ArchiveKind::from_name(&asset.name).is_some())
+    };
+
+    if !patterns.is_empty() {
+        return patterns.iter().find_map(|pattern| {
+            let pattern = expand(pattern, tool, version);
+            installable().find(|asset| wildcard_match(&pattern, &asset.name))
+        });
+    }
+
+    let triple = crate::target_triple();
+    let mut best: Option<(u32, &Asset)> = None;
+    for asset in installable() {
+        let score = if asset.name.contains(&triple) {
+            Some(100)
+        } else {
+            score(&asset.name, tool)
+        };
+        if let Some(score) = score {
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, asset));
+            }
+        }
+    }
+    best.map(|(_, asset)| asset)
+}
+
+/// Fills in `{tool}`, `{version}`, `{target}`, `{os}` and `{arch}`.
+fn expand(pattern: &str, tool: &str, version: &str) -> String {
+    pattern
+        .replace("{tool}", tool)
+        .replace("{version}", version)
+        .replace("{target}", &crate::target_triple())
+        .replace("{os}", std::env::consts::OS)
+        .replace("{arch}", std::env::consts::ARCH)
+}
+
+/// Matches `name` against `pattern`, where `*` stands for any run of
+/// characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// How well an asset name fits this machine, or `None` if it is built for
+/// something else.
+fn score(name: &str, tool: &str) -> Option<u32> {
+    let lower = name.to_lowercase();
+    let lower = lower
+        .strip_prefix(&tool.to_lowercase())
+        .unwrap_or(&lower)
+        .replace("x86_64", "amd64")
+        .replace("x86-64", "amd64");
+    let tokens: Vec<&str> = lower
+        .split(['-', '_', '.'])
+        .filter(|token| !token.is_empty())
+        .collect();
+    let has = |aliases: &[&str]| tokens.iter().any(|token| aliases.contains(token));
+
+    let os = std::env::consts::OS;
+    let arch = std::env::consts::ARCH;
+    let mut score = 0;
+
+    let (_, os_aliases) = OS_ALIASES.iter().find(|(name, _)| *name == os)?;
+    let names_os = has(os_aliases) || (os == "windows" && lower.ends_with(".exe"));
+    if !names_os {
+        return None;
+    }
+    if OS_ALIASES
+        .iter()
+        .any(|(other, aliases)| *other != os && has(aliases) && !has(os_aliases))
+    {
+        return None;
+    }
+
+    match ARCH_ALIASES.iter().find(|(name, _)| *name == arch) {
+        Some((_, aliases)) if has(aliases) => score += 10,
+        _ if ARCH_ALIASES
+            .iter()
+            .any(|(other, aliases)| *other != arch && has(aliases)) =>
+        {
+            return None
+        }
+        _ if os == "macos" && has(&["universal", "universal2"]) => score += 8,
+        _ => score += 1,
+    }
+
+    let musl = cfg!(target_env = "musl");
+    match os {
+        "linux" if has(&["musl"]) => score += if musl { 5 } else { 2 },
+        "linux" if has(&["gnu", "glibc"]) => {
+            if musl {
+                return None;
+            }
+            score += 5;
+        }
+        "linux" => score += 3,
+        "windows" if has(&["msvc"]) => score += if cfg!(target_env = "msvc") { 5 } else { 2 },
+        "windows" if has(&["gnu", "mingw"]) => {
+            score += if cfg!(target_env = "gnu") { 5 } else { 2 }
+        }
+        _ => {}
+    }
+
+    let native = if cfg!(windows) { ".zip" } else { ".tar.gz" };
+    if lower.ends_with(native) {
+        score += 1;
+    }
+
+    Some(score)
+}
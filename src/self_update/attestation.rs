@@ -0,0 +1,69 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::{describe, download, replace, SelfUpdateError};
+use crate::install::find_executable;
+use crate::Asset;
+
+const GITHUB_ACTIONS_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+/// Verifies the Sigstore attestation of `file` as built by a workflow in the
+/// `owner/repo` GitHub repository.
+///
+/// `gh attestation verify` is used when the GitHub CLI is installed, as it
+/// fetches the attestation itself. Otherwise `cosign` checks a
+/// `<asset>.sigstore.json` (or `.bundle`) bundle attached to the release.
+pub(crate) fn verify(
+    tool: &str,
+    repo: &str,
+    file: &Path,
+    asset: &Asset,
+    assets: &[Asset],
+) -> Result<(), SelfUpdateError> {
+    if let Some(gh) = find_executable("gh") {
+        let mut command = Command::new(gh);
+        command.arg("attestation").arg("verify").arg(file);
+        command.args(["--repo", repo]);
+        return run(command);
+    }
+
+    let cosign = find_executable("cosign").ok_or(SelfUpdateError::NoAttestationVerifier)?;
+    let bundle_asset = [".sigstore.json", ".bundle"]
+        .iter()
+        .find_map(|suffix| {
+            let name = format!("{}{}", asset.name, suffix);
+            assets.iter().find(|candidate| candidate.name == name)
+        })
+        .ok_or_else(|| SelfUpdateError::AttestationMissing {
+            asset: asset.name.clone(),
+        })?;
+
+    let bundle = replace::sibling(file, ".sigstore.json");
+    let result = download::download(tool, bundle_asset, &bundle, |_, _| {}).and_then(|_| {
+        let mut command = Command::new(cosign);
+        command.arg("verify-blob-attestation").arg(file);
+        command.arg("--bundle").arg(&bundle);
+        command.args([
+            "--new-bundle-format",
+            "--certificate-oidc-issuer",
+            GITHUB_ACTIONS_ISSUER,
+            "--certificate-identity-regexp",
+            &format!("^https://github.com/{}/", repo),
+        ]);
+        run(command)
+    });
+    let _ = std::fs::remove_file(&bundle);
+    result
+}
+
+fn run(mut command: Command) -> Result<(), SelfUpdateError> {
+    let status = command.stdout(Stdio::null()).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(SelfUpdateError::AttestationFailed {
+            command: describe(&command),
+            status,
+        })
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use super::SelfUpdateError;
+use crate::Asset;
+
+/// Names of release assets that list checksums for several files, in the
+/// `sha256sum` output format.
+const CHECKSUM_LISTS: &[&str] = &[
+    "SHA256SUMS",
+    "SHA256SUMS.txt",
+    "sha256sums.txt",
+    "checksums.txt",
+    "checksums.sha256",
+];
+
+/// A published SHA-256, and the checksum file it was read from, if any.
+pub(crate) struct Checksum {
+    pub(crate) digest: String,
+    pub(crate) manifest: Option<Manifest>,
+}
+
+/// A checksum file attached to the release.
+pub(crate) struct Manifest {
+    pub(crate) name: String,
+    pub(crate) contents: String,
+}
+
+/// Finds the expected SHA-256 of `asset`: from the asset itself when the
+/// source provides it, otherwise from a `<asset>.sha256` file or a checksum
+/// list published alongside it.
+pub(crate) fn expected_sha256(
+    tool: &str,
+    asset: &Asset,
+    assets: &[Asset],
+) -> Result<Option<Checksum>, SelfUpdateError> {
+    if let Some(ref digest) = asset.sha256 {
+        return Ok(Some(Checksum {
+            digest: digest.to_ascii_lowercase(),
+            manifest: None,
+        }));
+    }
+
+    let sidecar_name = format!("{}.sha256", asset.name);
+    let candidates = assets.iter().filter(|candidate| {
+        candidate.name == sidecar_name || CHECKSUM_LISTS.contains(&candidate.name.as_str())
+    });
+
+    for candidate in candidates {
+        let contents = fetch_text(tool, &candidate.url)?;
+        if let Some(digest) = find_digest(&contents, &asset.name) {
+            return Ok(Some(Checksum {
+                digest,
+                manifest: Some(Manifest {
+                    name: candidate.name.clone(),
+                    contents,
+                }),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Looks up `name` in `sha256sum`-style output. A lone digest with no file
+/// name, as found in per-asset `.sha256` files, matches any name.
+fn find_digest(listing: &str, name: &str) -> Option<String> {
+    listing.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let digest = fields.next()?;
+        let file = fields.next().map(|f| f.trim_start_matches('*'));
+        let is_digest = digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit());
+        (is_digest && file.is_none_or(|file| file == name || file.ends_with(&format!("/{}", name))))
+            .then(|| digest.to_ascii_lowercase())
+    })
+}
+
+pub(crate) fn fetch_text(tool: &str, url: &str) -> Result<String, SelfUpdateError> {
+    crate::source::http_client(tool)
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|err| SelfUpdateError::Download(err.to_string()))
+}
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Checks the downloaded `file` against `expected`, refusing to go on when
+/// they differ (or when no checksum was found and one is `required`).
+pub(crate) fn verify(
+    file: &Path,
+    asset_name: &str,
+    expected: Option<&str>,
+    required: bool,
+) -> Result<(), SelfUpdateError> {
+    let Some(expected) = expected else {
+        return if required {
+            Err(SelfUpdateError::ChecksumMissing {
+                asset: asset_name.to_string(),
+            })
+        } else {
+            Ok(())
+        };
+    };
+
+    let actual = sha256_file(file)?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(SelfUpdateError::ChecksumMismatch {
+            asset: asset_name.to_string(),
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use super::download::make_executable;
+use super::SelfUpdateError;
+use crate::Asset;
+
+/// Finds a binary patch from `current` to `latest` built for this target,
+/// named like `my-tool-1.2.0-to-1.3.0-x86_64-unknown-linux-gnu.bsdiff.gz`.
+/// Both versions must be exactly those, so that a patch from `11.2.0` or to
+/// `1.3.0.1` isn't taken for one from `1.2.0` to `1.3.0`.
+pub(crate) fn select_patch<'a>(
+    assets: &'a [Asset],
+    tool: &str,
+    current: &str,
+    latest: &str,
+) -> Option<&'a Asset> {
+    let target = crate::target_triple();
+    assets
+        .iter()
+        .find(|asset| patch_versions(&asset.name, tool, &target) == Some((current, latest)))
+}
+
+/// The versions a patch named `<tool>-<from>-to-<to>-<target>.bsdiff` (or
+/// `.bsdiff.gz`) goes between.
+fn patch_versions<'a>(name: &'a str, tool: &str, target: &str) -> Option<(&'a str, &'a str)> {
+    let stem = name.strip_suffix(".gz").unwrap_or(name);
+    stem.strip_suffix(".bsdiff")?
+        .strip_prefix(tool)?
+        .strip_prefix('-')?
+        .strip_suffix(target)?
+        .strip_suffix('-')?
+        .split_once("-to-")
+}
+
+/// The name under which a release publishes the checksum of the executable
+/// its patches produce, `<tool>-<latest>-<target>`, as if it were a bare
+/// asset: the patched file is checked against it before it is run.
+pub(crate) fn patched_name(tool: &str, latest: &str) -> String {
+    format!(
+        "{}-{}-{}{}",
+        tool,
+        latest,
+        crate::target_triple(),
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Applies the downloaded `patch` to `exe`, writing the result to `dest`.
+pub(crate) fn apply(
+    exe: &Path,
+    patch: &Path,
+    patch_name: &str,
+    dest: &Path,
+) -> Result<(), SelfUpdateError> {
+    let old = fs::read(exe)?;
+    let file = BufReader::new(File::open(patch)?);
+    let mut reader: Box<dyn Read> = if patch_name.ends_with(".gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let mut new = Vec::new();
+    bsdiff::patch(&old, &mut reader, &mut new).map_err(|err| {
+        SelfUpdateError::Archive(format!("could not apply {}: {}", patch_name, err))
+    })?;
+    fs::write(dest, new)?;
+    make_executable(dest)?;
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use super::SelfUpdateError;
+use crate::{http, Asset};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveKind {
+    TarGz,
+    Zip,
+    /// The asset is the executable itself.
+    Bare,
+}
+
+impl ArchiveKind {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        const NOT_EXECUTABLE: &[&str] = &[
+            ".sha256", ".sha512", ".sig", ".minisig", ".asc", ".pem", ".txt", ".json", ".exe",
+            ".msi", ".dmg", ".pkg", ".deb", ".rpm", ".gz", ".xz", ".bz2", ".zst", ".bsdiff",
+        ];
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if cfg!(windows) {
+            name.ends_with(".exe").then_some(ArchiveKind::Bare)
+        } else if NOT_EXECUTABLE.iter().any(|suffix| name.ends_with(suffix)) {
+            None
+        } else {
+            Some(ArchiveKind::Bare)
+        }
+    }
+}
+
+/// How many times an interrupted download is resumed before giving up.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Downloads `asset` to `dest`, calling `progress` with the bytes received so
+/// far and the expected total.
+///
+/// Interrupted downloads are resumed with HTTP range requests, both within
+/// this call and across runs: the partial file is kept along with a
+/// `<dest>.resume` file recording the URL and the server's validator
+/// (`ETag` or `Last-Modified`), so a changed asset is fetched from scratch.
+pub(crate) fn download(
+    tool: &str,
+    asset: &Asset,
+    dest: &Path,
+    mut progress: impl FnMut(u64, Option<u64>),
+) -> Result<(), SelfUpdateError> {
+    let client = http::Client::new(format!("{}/self-update", tool), None);
+
+    let resume_file = super::replace::sibling(dest, ".resume");
+    let mut attempt = 1;
+    loop {
+        match fetch(&client, asset, dest, &resume_file, &mut progress) {
+            Ok(()) => {
+                let _ = fs::remove_file(&resume_file);
+                return Ok(());
+            }
+            Err(Fetch::Retry(_)) if attempt < MAX_ATTEMPTS => {
+                std::thread::sleep(Duration::from_secs(u64::from(attempt)));
+                attempt += 1;
+            }
+            Err(Fetch::Retry(err)) | Err(Fetch::Fatal(err)) => return Err(err),
+        }
+    }
+}
+
+enum Fetch {
+    /// The connection dropped or the server had a hiccup; worth resuming.
+    Retry(SelfUpdateError),
+    Fatal(SelfUpdateError),
+}
+
+impl From<io::Error> for Fetch {
+    fn from(err: io::Error) -> Self {
+        Fetch::Fatal(err.into())
+    }
+}
+
+/// One attempt at downloading `asset`, picking up from what is already in
+/// `dest` when the server allows it.
+fn fetch(
+    client: &http::Client,
+    asset: &Asset,
+    dest: &Path,
+    resume_file: &Path,
+    progress: &mut impl FnMut(u64, Option<u64>),
+) -> Result<(), Fetch> {
+    let retry = |err: http::Error| Fetch::Retry(SelfUpdateError::Download(err.to_string()));
+
+    let validator = fs::read_to_string(resume_file).ok().and_then(|contents| {
+        let (url, validator) = contents.split_once('\n')?;
+        (url == asset.url).then(|| validator.trim().to_string())
+    });
+    let offset = match validator {
+        Some(_) => fs::metadata(dest).map(|meta| meta.len()).unwrap_or(0),
+        None => 0,
+    };
+
+    let mut request = client.get(&asset.url);
+    if let (Some(validator), true) = (&validator, offset > 0) {
+        request = request
+            .header("Range", format!("bytes={}-", offset))
+            .header("If-Range", validator.as_str());
+    }
+    let mut response = request.send().map_err(retry)?;
+
+    let status = response.status();
+    let status_error = || SelfUpdateError::Download(format!("HTTP status {}", status));
+    if status == 416 {
+        // The partial file doesn't fit the asset any more; start over.
+        let _ = fs::remove_file(resume_file);
+        let _ = fs::remove_file(dest);
+        return Err(Fetch::Retry(status_error()));
+    }
+    if status >= 500 {
+        return Err(Fetch::Retry(status_error()));
+    }
+    if status >= 400 {
+        return Err(Fetch::Fatal(status_error()));
+    }
+    let resumed = status == 206;
+
+    let mut file = if resumed {
+        fs::OpenOptions::new().append(true).open(dest)?
+    } else {
+        let validator = response
+            .header("ETag")
+            .or_else(|| response.header("Last-Modified"));
+        match validator {
+            Some(validator) => fs::write(resume_file, format!("{}\n{}", asset.url, validator))?,
+            None => {
+                let _ = fs::remove_file(resume_file);
+            }
+        }
+        File::create(dest)?
+    };
+
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = response
+        .content_length()
+        .map(|length| length + downloaded)
+        .or(asset.size);
+    let mut buffer = [0; 64 * 1024];
+    progress(downloaded, total);
+    loop {
+        let read = response
+            .read(&mut buffer)
+            .map_err(|err| Fetch::Retry(SelfUpdateError::Download(err.to_string())))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        downloaded += read as u64;
+        progress(downloaded, total);
+    }
+
+    match total {
+        Some(total) if downloaded < total => Err(Fetch::Retry(SelfUpdateError::Download(format!(
+            "connection closed after {} of {} bytes",
+            downloaded, total
+        )))),
+        _ => Ok(()),
+    }
+}
+
+/// Extracts the executable called `exe_name` from the downloaded `archive`
+/// into `dest`, and makes it executable.
+pub(crate) fn extract(
+    archive: &Path,
+    kind: ArchiveKind,
+    exe_name: &str,
+    dest: &Path,
+) -> Result<(), SelfUpdateError> {
+    let missing = || SelfUpdateError::Archive(format!("`{}` not found in the archive", exe_name));
+
+    match kind {
+        ArchiveKind::Bare => {
+            fs::copy(archive, dest)?;
+        }
+        ArchiveKind::TarGz => {
+            let decoder = flate2::read::GzDecoder::new(File::open(archive)?);
+            let mut tar = tar::Archive::new(decoder);
+            let mut found = false;
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if entry.path()?.file_name().and_then(|n| n.to_str()) == Some(exe_name) {
+                    io::copy(&mut entry, &mut File::create(dest)?)?;
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Err(missing());
+            }
+        }
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(File::open(archive)?)
+                .map_err(|err| SelfUpdateError::Archive(err.to_string()))?;
+            let index = (0..zip.len())
+                .find(|&i| {
+                    zip.name_for_index(i)
+                        .and_then(Result::ok)
+                        .is_some_and(|name| {
+                            Path::new(name.as_ref())
+                                .file_name()
+                                .and_then(|n| n.to_str())
+                                == Some(exe_name)
+                        })
+                })
+                .ok_or_else(missing)?;
+            let mut entry = zip
+                .by_index(index)
+                .map_err(|err| SelfUpdateError::Archive(err.to_string()))?;
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            fs::write(dest, contents)?;
+        }
+    }
+
+    make_executable(dest)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::io;
+use std::path::Path;
+use std::process::ExitStatus;
+
+/// Runs `exe` with `args` with administrator rights through a UAC prompt,
+/// waiting for it to finish.
+#[cfg(windows)]
+pub(crate) fn run_elevated(exe: &Path, args: &[String]) -> io::Result<ExitStatus> {
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+    let arguments = args
+        .iter()
+        .map(|arg| {
+            if arg.contains(' ') {
+                quote(&format!("\"{}\"", arg))
+            } else {
+                quote(arg)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut script = format!(
+        "$p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru",
+        quote(&exe.to_string_lossy())
+    );
+    if !arguments.is_empty() {
+        script.push_str(&format!(" -ArgumentList @({})", arguments));
+    }
+    script.push_str("; exit $p.ExitCode");
+
+    std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()
+}
+
+#[cfg(not(windows))]
+pub(crate) fn run_elevated(_exe: &Path, _args: &[String]) -> io::Result<ExitStatus> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "elevation is only supported on Windows",
+    ))
+}
+
+/// What to tell the user when the executable's directory isn't writable.
+pub(crate) fn hint() -> &'static str {
+    if cfg!(windows) {
+        "run the command again from a terminal opened with \"Run as administrator\""
+    } else {
+        "run the command again with sudo, or reinstall the tool in a directory you own"
+    }
+}
@@ -0,0 +1,1179 @@
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::install::{find_executable, InstallMethod};
+use crate::VersionChecker;
+
+#[cfg(feature = "self-update-download")]
+mod assets;
+#[cfg(feature = "self-update-attestation")]
+mod attestation;
+#[cfg(feature = "self-update-download")]
+mod checksum;
+#[cfg(feature = "self-update-delta")]
+mod delta;
+#[cfg(feature = "self-update-download")]
+mod download;
+mod elevate;
+mod replace;
+#[cfg(feature = "self-update-download")]
+mod signature;
+
+/// Suffixes of the executable staged by `stage_update`, and of the file
+/// recording its version.
+#[cfg(feature = "self-update-download")]
+const STAGED: &str = ".staged";
+#[cfg(feature = "self-update-download")]
+const STAGED_VERSION: &str = ".staged-version";
+
+/// How long the updated tool gets to answer `--version`.
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What [`VersionChecker::self_update`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelfUpdateOutcome {
+    /// The running version is already the latest one.
+    UpToDate { version: String },
+    /// The tool was replaced by a newer version.
+    Updated { from: String, to: String },
+}
+
+/// What a self-update would do, as worked out by
+/// [`VersionChecker::self_update_dry_run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfUpdatePlan {
+    pub from: String,
+    pub to: String,
+    pub method: UpdateMethod,
+    /// The executable that would be replaced.
+    pub destination: PathBuf,
+}
+
+/// How a [`SelfUpdatePlan`] would install the new version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateMethod {
+    /// Running an installer such as `cargo binstall`.
+    Installer { command: String },
+    /// Downloading a release asset.
+    Download { asset: String, url: String },
+}
+
+/// Why a self-update failed.
+#[derive(Debug)]
+pub enum SelfUpdateError {
+    /// The latest release could not be looked up.
+    LatestUnknown,
+    /// Neither `cargo-binstall` nor `cargo` is on the `PATH`.
+    NoInstaller,
+    /// The installer ran but reported a failure.
+    InstallerFailed {
+        command: String,
+        status: ExitStatus,
+    },
+    /// The updated tool does not report the expected version.
+    VerificationFailed {
+        expected: String,
+        found: String,
+        restored: bool,
+    },
+    /// The updated tool could not be run, e.g. because the download was
+    /// truncated or built for another architecture.
+    BrokenUpdate {
+        reason: String,
+        restored: bool,
+    },
+    /// The release has no asset built for this target.
+    NoMatchingAsset {
+        target: String,
+    },
+    Download(String),
+    /// The downloaded archive could not be unpacked.
+    Archive(String),
+    /// The downloaded asset does not match its published SHA-256.
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+    /// No checksum was published for the asset, and one is required.
+    ChecksumMissing {
+        asset: String,
+    },
+    /// The configured signing key could not be parsed.
+    InvalidPublicKey(String),
+    /// A signing key is configured but the release has no signature for the
+    /// asset or its checksum file.
+    SignatureMissing {
+        asset: String,
+    },
+    /// The signature does not verify against the configured key.
+    SignatureInvalid {
+        file: String,
+        reason: String,
+    },
+    /// Neither `gh` nor `cosign` is on the `PATH` to check the attestation.
+    NoAttestationVerifier,
+    /// The release has no Sigstore bundle for the asset.
+    AttestationMissing {
+        asset: String,
+    },
+    /// The attestation did not verify.
+    AttestationFailed {
+        command: String,
+        status: ExitStatus,
+    },
+    /// The release archive lacks a companion binary.
+    MissingCompanion {
+        name: String,
+    },
+    /// There is no previous executable to roll back to.
+    NoPreviousVersion,
+    /// The executable isn't in a `bin` directory, so cargo can't install
+    /// over it.
+    OutsideInstallRoot {
+        exe: PathBuf,
+    },
+    /// The directory holding the executable can't be written to without
+    /// more privileges.
+    PermissionDenied {
+        dir: PathBuf,
+    },
+    Io(std::io::Error),
+}
+
+impl fmt::Display for SelfUpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelfUpdateError::LatestUnknown => write!(f, "could not determine the latest version"),
+            SelfUpdateError::NoInstaller => {
+                write!(f, "neither cargo-binstall nor cargo was found on the PATH")
+            }
+            SelfUpdateError::InstallerFailed { command, status } => {
+                write!(f, "`{}` failed ({})", command, status)
+            }
+            SelfUpdateError::VerificationFailed {
+                expected,
+                found,
+                restored,
+            } => {
+                write!(
+                    f,
+                    "expected version {} after updating, but the tool reports `{}`",
+                    expected, found
+                )?;
+                write_restored(f, *restored)
+            }
+            SelfUpdateError::BrokenUpdate { reason, restored } => {
+                write!(f, "the updated tool does not run: {}", reason)?;
+                write_restored(f, *restored)
+            }
+            SelfUpdateError::NoMatchingAsset { target } => {
+                write!(f, "the latest release has no asset for {}", target)
+            }
+            SelfUpdateError::Download(err) => write!(f, "download failed: {}", err),
+            SelfUpdateError::Archive(err) => write!(f, "could not unpack the release: {}", err),
+            SelfUpdateError::ChecksumMismatch {
+                asset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "checksum mismatch for {} (expected {}, got {}); refusing to update",
+                asset, expected, actual
+            ),
+            SelfUpdateError::ChecksumMissing { asset } => {
+                write!(f, "no SHA-256 checksum published for {}", asset)
+            }
+            SelfUpdateError::InvalidPublicKey(err) => {
+                write!(f, "invalid signing key: {}", err)
+            }
+            SelfUpdateError::SignatureMissing { asset } => {
+                write!(f, "no minisign signature published for {}", asset)
+            }
+            SelfUpdateError::SignatureInvalid { file, reason } => write!(
+                f,
+                "bad signature on {} ({}); refusing to update",
+                file, reason
+            ),
+            SelfUpdateError::NoAttestationVerifier => write!(
+                f,
+                "neither gh nor cosign was found on the PATH to verify the attestation"
+            ),
+            SelfUpdateError::AttestationMissing { asset } => {
+                write!(f, "no Sigstore attestation published for {}", asset)
+            }
+            SelfUpdateError::AttestationFailed { command, status } => write!(
+                f,
+                "attestation check `{}` failed ({}); refusing to update",
+                command, status
+            ),
+            SelfUpdateError::PermissionDenied { dir } => write!(
+                f,
+                "no permission to replace files in {}; {}",
+                dir.display(),
+                elevate::hint()
+            ),
+            SelfUpdateError::MissingCompanion { name } => {
+                write!(f, "the release does not include {}", name)
+            }
+            SelfUpdateError::NoPreviousVersion => {
+                write!(f, "no previous version to roll back to")
+            }
+            SelfUpdateError::OutsideInstallRoot { exe } => write!(
+                f,
+                "{} is not in a bin directory cargo can install into; download the update instead",
+                exe.display()
+            ),
+            SelfUpdateError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+fn write_restored(f: &mut fmt::Formatter<'_>, restored: bool) -> fmt::Result {
+    if restored {
+        write!(f, "; the previous version was restored")
+    } else {
+        Ok(())
+    }
+}
+
+impl std::error::Error for SelfUpdateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SelfUpdateError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SelfUpdateError {
+    fn from(err: std::io::Error) -> Self {
+        SelfUpdateError::Io(err)
+    }
+}
+
+impl VersionChecker {
+    /// Updates the tool to its latest release with `cargo binstall`, or
+    /// `cargo install --locked` when cargo-binstall isn't installed.
+    ///
+    /// The installer's output goes straight to the terminal. Afterwards the
+    /// executable is run with `--version` to check that the new version is
+    /// in place; if it isn't, the previous executable is put back.
+    ///
+    /// With [`delegate_to_package_manager`](Self::delegate_to_package_manager),
+    /// package-managed installs are upgraded by their package manager instead,
+    /// and the outcome is the version it installed, which may be older than
+    /// the latest release.
+    pub fn self_update(&self) -> Result<SelfUpdateOutcome, SelfUpdateError> {
+        self.report(UpdateProgress::Checking);
+        let latest = self
+            .source
+            .latest_release(&self.tool_name)
+            .ok_or(SelfUpdateError::LatestUnknown)?
+            .version;
+
+        if !self
+            .version_policy
+            .is_update(&self.current_version, &latest)
+        {
+            return Ok(SelfUpdateOutcome::UpToDate {
+                version: self.current_version.clone(),
+            });
+        }
+
+        if let Some(mut command) = self.package_manager_command() {
+            self.report(UpdateProgress::Installing);
+            let status = command.status()?;
+            if !status.success() {
+                return Err(SelfUpdateError::InstallerFailed {
+                    command: describe(&command),
+                    status,
+                });
+            }
+            // The package manager may have moved the executable, e.g. to a
+            // new `Cellar` directory, so look for it where the shell would.
+            let exe = match find_executable(&self.tool_name) {
+                Some(exe) => exe,
+                None => self.target_exe()?,
+            };
+            // Package managers, distributions especially, may lag behind the
+            // latest release: what they installed is the update.
+            let installed = match verify_version(&exe, &latest) {
+                Ok(()) => latest,
+                Err(SelfUpdateError::VerificationFailed {
+                    expected, found, ..
+                }) => reported_version(&found).ok_or(SelfUpdateError::VerificationFailed {
+                    expected,
+                    found,
+                    restored: false,
+                })?,
+                Err(err) => return Err(err),
+            };
+            if !self
+                .version_policy
+                .is_update(&self.current_version, &installed)
+            {
+                return Ok(SelfUpdateOutcome::UpToDate {
+                    version: self.current_version.clone(),
+                });
+            }
+            return Ok(SelfUpdateOutcome::Updated {
+                from: self.current_version.clone(),
+                to: installed,
+            });
+        }
+
+        let exe = self.target_exe()?;
+        let mut command = installer_command(&self.tool_name, &latest, &exe)?;
+        if let Some(outcome) = self.ensure_writable(&exe, &latest)? {
+            return Ok(outcome);
+        }
+        replace::backup(&exe)?;
+
+        self.report(UpdateProgress::Installing);
+        let status = command.status()?;
+        if !status.success() {
+            replace::restore(&exe)?;
+            return Err(SelfUpdateError::InstallerFailed {
+                command: describe(&command),
+                status,
+            });
+        }
+
+        self.verify_or_restore(&exe, &latest)?;
+
+        Ok(SelfUpdateOutcome::Updated {
+            from: self.current_version.clone(),
+            to: latest,
+        })
+    }
+
+    /// Works out what [`self_update`](Self::self_update) would do, without
+    /// running anything. Returns `None` when already up to date.
+    pub fn self_update_dry_run(&self) -> Result<Option<SelfUpdatePlan>, SelfUpdateError> {
+        let latest = self
+            .source
+            .latest_release(&self.tool_name)
+            .ok_or(SelfUpdateError::LatestUnknown)?
+            .version;
+
+        if !self
+            .version_policy
+            .is_update(&self.current_version, &latest)
+        {
+            return Ok(None);
+        }
+
+        let command = match self.package_manager_command() {
+            Some(command) => command,
+            None => installer_command(&self.tool_name, &latest, &self.target_exe()?)?,
+        };
+        Ok(Some(SelfUpdatePlan {
+            from: self.current_version.clone(),
+            to: latest,
+            method: UpdateMethod::Installer {
+                command: describe(&command),
+            },
+            destination: self.target_exe()?,
+        }))
+    }
+
+    /// When the tool was installed by Homebrew, Scoop, winget or apt, makes
+    /// [`self_update`](Self::self_update) run that package manager's upgrade
+    /// command rather than replacing files it manages. Other installs are
+    /// updated as usual.
+    pub fn delegate_to_package_manager(mut self, delegate: bool) -> Self {
+        self.update_options.package_manager = delegate;
+        self
+    }
+
+    /// The package manager command to update with, when delegating to it.
+    fn package_manager_command(&self) -> Option<Command> {
+        if !self.update_options.package_manager {
+            return None;
+        }
+        let method = match self.update_options.executable {
+            Some(ref exe) => InstallMethod::from_path(exe),
+            None => InstallMethod::detect(),
+        };
+        package_manager_command(method, &self.tool_name, &self.target_exe().ok()?)
+    }
+
+    /// Updates the executable at `path` rather than the running one, for
+    /// tools that update others. Package-managed installs are then told
+    /// apart by where `path` is.
+    pub fn executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.update_options.executable = Some(path.into());
+        self
+    }
+
+    /// The executable a self-update replaces.
+    fn target_exe(&self) -> std::io::Result<PathBuf> {
+        match self.update_options.executable {
+            Some(ref exe) => exe.canonicalize(),
+            None => std::env::current_exe()?.canonicalize(),
+        }
+    }
+
+    /// On Windows, when the executable lives in a directory that needs
+    /// administrator rights (such as `Program Files`), re-runs the current
+    /// command through a UAC prompt instead of failing. Elsewhere, and when
+    /// this is off, the error explains how to re-run the update.
+    pub fn elevate_if_needed(mut self, elevate: bool) -> Self {
+        self.update_options.elevate = elevate;
+        self
+    }
+
+    /// Checks that `exe` can be replaced. When it can't and
+    /// [`elevate_if_needed`](Self::elevate_if_needed) is on, this command is
+    /// re-run elevated, and its outcome returned.
+    fn ensure_writable(
+        &self,
+        exe: &Path,
+        latest: &str,
+    ) -> Result<Option<SelfUpdateOutcome>, SelfUpdateError> {
+        match replace::ensure_writable(exe) {
+            Ok(()) => return Ok(None),
+            Err(err) if err.kind() != std::io::ErrorKind::PermissionDenied => {
+                return Err(err.into())
+            }
+            Err(_) => {}
+        }
+
+        let dir = exe.parent().unwrap_or(exe).to_path_buf();
+        if !self.update_options.elevate || !cfg!(windows) {
+            return Err(SelfUpdateError::PermissionDenied { dir });
+        }
+
+        // The running command is re-run, which may be updating another
+        // executable than itself.
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let status = elevate::run_elevated(&std::env::current_exe()?, &args)?;
+        if !status.success() {
+            return Err(SelfUpdateError::InstallerFailed {
+                command: format!("{} {} (elevated)", self.tool_name, args.join(" ")),
+                status,
+            });
+        }
+        verify_version(exe, latest)?;
+        Ok(Some(SelfUpdateOutcome::Updated {
+            from: self.current_version.clone(),
+            to: latest.to_string(),
+        }))
+    }
+
+    /// Calls `progress` as a self-update moves through its stages and while
+    /// the release asset downloads, so hosts can draw their own progress bar.
+    pub fn on_update_progress(
+        mut self,
+        progress: impl Fn(UpdateProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.update_options.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// The executables a self-update replaces: `exe` followed by its
+    /// companions.
+    fn update_targets(&self, exe: &Path) -> Vec<PathBuf> {
+        std::iter::once(exe.to_path_buf())
+            .chain(self.update_options.companions.iter().map(|name| {
+                exe.with_file_name(format!("{}{}", name, std::env::consts::EXE_SUFFIX))
+            }))
+            .collect()
+    }
+
+    /// Restores `exe` and its companions from their `.old` copies. Returns
+    /// whether `exe` itself had one.
+    fn restore_all(&self, exe: &Path) -> std::io::Result<bool> {
+        let targets = self.update_targets(exe);
+        for companion in &targets[1..] {
+            let _ = replace::restore(companion);
+        }
+        replace::restore(exe)
+    }
+
+    /// Verifies the updated executable, putting the previous executables back
+    /// if it does not report `expected`.
+    fn verify_or_restore(&self, exe: &Path, expected: &str) -> Result<(), SelfUpdateError> {
+        verify_version(exe, expected).map_err(|mut err| {
+            let was_restored = self.restore_all(exe).unwrap_or(false);
+            if let SelfUpdateError::VerificationFailed { restored, .. }
+            | SelfUpdateError::BrokenUpdate { restored, .. } = &mut err
+            {
+                *restored = was_restored;
+            }
+            err
+        })
+    }
+
+    fn report(&self, progress: UpdateProgress) {
+        if let Some(ref callback) = self.update_options.progress {
+            callback(progress);
+        }
+    }
+
+    /// Puts back the executable that the last self-update replaced, which is
+    /// kept next to it as `<exe>.old`.
+    pub fn rollback(&self) -> Result<(), SelfUpdateError> {
+        let exe = self.target_exe()?;
+        if self.restore_all(&exe)? {
+            Ok(())
+        } else {
+            Err(SelfUpdateError::NoPreviousVersion)
+        }
+    }
+}
+
+/// Where a self-update is at, reported to the callback registered with
+/// [`VersionChecker::on_update_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateProgress {
+    /// Looking up the latest release.
+    Checking,
+    /// Downloading the release asset. `total` is `None` when the server
+    /// doesn't send a length.
+    Downloading { downloaded: u64, total: Option<u64> },
+    /// Checking the download's checksum, signature or attestation.
+    Verifying,
+    /// Running the installer, or unpacking and swapping in the new executable.
+    Installing,
+}
+
+type ProgressFn = Box<dyn Fn(UpdateProgress) + Send + Sync>;
+
+/// Settings for self-updates, set through [`VersionChecker`] builder methods.
+#[derive(Default)]
+pub(crate) struct UpdateOptions {
+    progress: Option<ProgressFn>,
+    executable: Option<PathBuf>,
+    elevate: bool,
+    package_manager: bool,
+    companions: Vec<String>,
+    #[cfg(feature = "self-update-download")]
+    pub(crate) require_checksum: bool,
+    #[cfg(feature = "self-update-download")]
+    pub(crate) signing_key: Option<String>,
+    #[cfg(feature = "self-update-download")]
+    pub(crate) asset_patterns: Vec<String>,
+    #[cfg(feature = "self-update-attestation")]
+    pub(crate) attestation_repo: Option<String>,
+}
+
+#[cfg(feature = "self-update-download")]
+impl VersionChecker {
+    /// Works out what [`self_update_download`](Self::self_update_download)
+    /// would do, without downloading anything. Returns `None` when already up
+    /// to date.
+    pub fn self_update_download_dry_run(&self) -> Result<Option<SelfUpdatePlan>, SelfUpdateError> {
+        let Some((release, asset)) = self.download_target()? else {
+            return Ok(None);
+        };
+
+        Ok(Some(SelfUpdatePlan {
+            from: self.current_version.clone(),
+            to: release.version,
+            method: UpdateMethod::Download {
+                asset: asset.name,
+                url: asset.url,
+            },
+            destination: self.target_exe()?,
+        }))
+    }
+
+    /// The latest release and the asset to install from it, or `None` when
+    /// already up to date.
+    fn download_target(&self) -> Result<Option<(crate::Release, crate::Asset)>, SelfUpdateError> {
+        let release = self
+            .source
+            .latest_release(&self.tool_name)
+            .ok_or(SelfUpdateError::LatestUnknown)?;
+
+        if !self
+            .version_policy
+            .is_update(&self.current_version, &release.version)
+        {
+            return Ok(None);
+        }
+
+        let target = crate::target_triple();
+        let asset = assets::select_asset(
+            &release.assets,
+            &self.tool_name,
+            &release.version,
+            &self.update_options.asset_patterns,
+        )
+        .ok_or(SelfUpdateError::NoMatchingAsset { target })?
+        .clone();
+        Ok(Some((release, asset)))
+    }
+
+    /// Refuses to self-update from an asset that has no published SHA-256
+    /// checksum. By default such assets are installed unverified.
+    pub fn require_checksum(mut self, require: bool) -> Self {
+        self.update_options.require_checksum = require;
+        self
+    }
+
+    /// Also updates `name`, a companion binary installed next to the tool
+    /// from the same release archive, in the same self-update. If the
+    /// archive doesn't contain every companion, nothing is replaced.
+    pub fn companion(mut self, name: impl Into<String>) -> Self {
+        self.update_options.companions.push(name.into());
+        self
+    }
+
+    /// Names the release asset to install, instead of guessing it from the
+    /// OS, architecture and libc in asset names. `{tool}`, `{version}`,
+    /// `{target}` (the target triple), `{os}` and `{arch}` are filled in and
+    /// `*` matches anything, e.g. `{tool}-{version}-{target}.tar.gz`.
+    ///
+    /// Can be called several times; patterns are tried in order.
+    pub fn asset_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.update_options.asset_patterns.push(pattern.into());
+        self
+    }
+
+    /// Only self-updates from assets signed with this minisign public key,
+    /// given in base64 as on the second line of `minisign.pub`.
+    ///
+    /// The release must carry either `<asset>.minisig`, or a `.minisig` for
+    /// the checksum file that lists the asset. Compile the key into the tool
+    /// so that a compromised mirror or CDN cannot ship its own.
+    pub fn signing_key(mut self, public_key: impl Into<String>) -> Self {
+        self.update_options.signing_key = Some(public_key.into());
+        self
+    }
+
+    /// Only self-updates from assets with a Sigstore build provenance
+    /// attestation from a GitHub Actions workflow in `repo` (`owner/name`),
+    /// as produced by `actions/attest-build-provenance`.
+    ///
+    /// The check runs `gh attestation verify`, or `cosign` against a
+    /// `<asset>.sigstore.json` bundle when the GitHub CLI isn't installed.
+    #[cfg(feature = "self-update-attestation")]
+    pub fn require_attestation(mut self, repo: impl Into<String>) -> Self {
+        self.update_options.attestation_repo = Some(repo.into());
+        self
+    }
+
+    /// Updates the tool by downloading the prebuilt asset for this target
+    /// from the latest release and swapping it in place of the running
+    /// executable, for users without a Rust toolchain.
+    ///
+    /// The source must list assets (e.g. [`GitHubReleases`](crate::GitHubReleases)).
+    /// Assets are matched on the target triple, or the OS, architecture and
+    /// libc, in their name (see [`asset_pattern`](Self::asset_pattern) to name
+    /// them explicitly) and may be a `.tar.gz`, a `.zip` or the bare
+    /// executable.
+    ///
+    /// The download is checked against its SHA-256 before anything is
+    /// replaced, using the digest from the source, a `<asset>.sha256` file, or
+    /// a `SHA256SUMS`/`checksums.txt` list attached to the release. See
+    /// [`require_checksum`](Self::require_checksum) for releases without one,
+    /// and [`signing_key`](Self::signing_key) to also check a signature.
+    pub fn self_update_download(&self) -> Result<SelfUpdateOutcome, SelfUpdateError> {
+        self.report(UpdateProgress::Checking);
+        let Some((release, asset)) = self.download_target()? else {
+            return Ok(SelfUpdateOutcome::UpToDate {
+                version: self.current_version.clone(),
+            });
+        };
+
+        let exe = self.target_exe()?;
+        if let Some(outcome) = self.ensure_writable(&exe, &release.version)? {
+            return Ok(outcome);
+        }
+        self.fetch_update(&release, &asset, &exe, ".new")?;
+
+        self.report(UpdateProgress::Installing);
+        self.replace_all(&exe, ".new")?;
+
+        self.verify_or_restore(&exe, &release.version)?;
+
+        Ok(SelfUpdateOutcome::Updated {
+            from: self.current_version.clone(),
+            to: release.version,
+        })
+    }
+
+    /// Downloads the latest release's executable and stages it next to the
+    /// running one, to be swapped in by [`apply_staged_update`] on the next
+    /// run. Returns the staged version, or `None` when already up to date.
+    ///
+    /// This blocks while downloading, so call it from a background thread to
+    /// keep the current run fast. The staged file only appears once it has
+    /// been fully downloaded and verified, so exiting halfway is harmless.
+    /// It sidesteps replacing a busy executable, which Windows doesn't allow.
+    ///
+    /// [`apply_staged_update`]: Self::apply_staged_update
+    pub fn stage_update(&self) -> Result<Option<String>, SelfUpdateError> {
+        let Some((release, asset)) = self.download_target()? else {
+            return Ok(None);
+        };
+
+        let exe = self.target_exe()?;
+        replace::ensure_writable(&exe).map_err(|err| permission_error(&exe, err))?;
+        self.fetch_update(&release, &asset, &exe, ".staging")?;
+
+        let targets = self.update_targets(&exe);
+        let result = std::fs::write(replace::sibling(&exe, STAGED_VERSION), &release.version)
+            .and_then(|_| {
+                // The main executable goes last: its staged file is what marks
+                // the update as ready.
+                targets.iter().rev().try_for_each(|target| {
+                    std::fs::rename(
+                        replace::sibling(target, ".staging"),
+                        replace::sibling(target, STAGED),
+                    )
+                })
+            });
+        if let Err(err) = result {
+            for target in &targets {
+                let _ = std::fs::remove_file(replace::sibling(target, ".staging"));
+                let _ = std::fs::remove_file(replace::sibling(target, STAGED));
+            }
+            return Err(err.into());
+        }
+        Ok(Some(release.version))
+    }
+
+    /// Swaps in an update staged by [`stage_update`](Self::stage_update), if
+    /// there is one. Call it first thing on startup: the new version is used
+    /// from the next invocation on, while this one carries on with the code
+    /// it already loaded.
+    ///
+    /// A staged version that isn't newer than the running one is discarded.
+    pub fn apply_staged_update(&self) -> Result<Option<SelfUpdateOutcome>, SelfUpdateError> {
+        let exe = self.target_exe()?;
+        let staged = replace::sibling(&exe, STAGED);
+        let version_file = replace::sibling(&exe, STAGED_VERSION);
+        if !staged.is_file() {
+            return Ok(None);
+        }
+
+        let version = std::fs::read_to_string(&version_file)
+            .map(|version| version.trim().to_string())
+            .unwrap_or_default();
+        let targets = self.update_targets(&exe);
+        let complete = targets
+            .iter()
+            .all(|target| replace::sibling(target, STAGED).is_file());
+        if !complete
+            || !self
+                .version_policy
+                .is_update(&self.current_version, &version)
+        {
+            for target in &targets {
+                let _ = std::fs::remove_file(replace::sibling(target, STAGED));
+            }
+            let _ = std::fs::remove_file(&version_file);
+            return Ok(None);
+        }
+
+        replace::ensure_writable(&exe).map_err(|err| permission_error(&exe, err))?;
+        self.report(UpdateProgress::Installing);
+        let result = self.replace_all(&exe, STAGED);
+        let _ = std::fs::remove_file(&version_file);
+        result?;
+
+        self.verify_or_restore(&exe, &version)?;
+
+        Ok(Some(SelfUpdateOutcome::Updated {
+            from: self.current_version.clone(),
+            to: version,
+        }))
+    }
+
+    /// Gets the new executables next to the ones they replace, as
+    /// `<exe><suffix>`: from a binary patch when the release has one for the
+    /// running version, otherwise from `asset`.
+    fn fetch_update(
+        &self,
+        release: &crate::Release,
+        asset: &crate::Asset,
+        exe: &Path,
+        suffix: &str,
+    ) -> Result<(), SelfUpdateError> {
+        #[cfg(feature = "self-update-delta")]
+        if self.update_options.companions.is_empty() {
+            if let Some(patch) = delta::select_patch(
+                &release.assets,
+                &self.tool_name,
+                &self.current_version,
+                &release.version,
+            ) {
+                let dest = replace::sibling(exe, suffix);
+                let patch_file = replace::sibling(exe, ".patch");
+                let patched = self
+                    .download_verified(release, patch, &patch_file)
+                    .and_then(|_| delta::apply(exe, &patch_file, &patch.name, &dest))
+                    // The running executable may not be the exact build the
+                    // patch was made against, so the result must match the
+                    // published checksum before it is even run.
+                    .and_then(|_| self.verify_patched(release, &dest))
+                    .and_then(|_| verify_version(&dest, &release.version));
+                let _ = std::fs::remove_file(&patch_file);
+                let _ = std::fs::remove_file(replace::sibling(&patch_file, ".resume"));
+                if patched.is_ok() {
+                    return Ok(());
+                }
+                let _ = std::fs::remove_file(&dest);
+            }
+        }
+
+        self.fetch_executable(release, asset, exe, suffix)
+    }
+
+    /// Checks the executable a patch produced at `dest` against the checksum
+    /// the release publishes for it, which is required.
+    #[cfg(feature = "self-update-delta")]
+    fn verify_patched(&self, release: &crate::Release, dest: &Path) -> Result<(), SelfUpdateError> {
+        let name = delta::patched_name(&self.tool_name, &release.version);
+        let asset = release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .cloned()
+            .unwrap_or_else(|| crate::Asset {
+                name: name.clone(),
+                url: String::new(),
+                size: None,
+                sha256: None,
+            });
+        let expected = checksum::expected_sha256(&self.tool_name, &asset, &release.assets)?;
+        checksum::verify(
+            dest,
+            &name,
+            expected.as_ref().map(|checksum| checksum.digest.as_str()),
+            true,
+        )
+    }
+
+    /// Downloads `asset`, verifies it, and extracts the executable replacing
+    /// `exe` and its companions into `<exe><suffix>`. Nothing is kept unless
+    /// every one of them is found.
+    fn fetch_executable(
+        &self,
+        release: &crate::Release,
+        asset: &crate::Asset,
+        exe: &Path,
+        suffix: &str,
+    ) -> Result<(), SelfUpdateError> {
+        let kind = download::ArchiveKind::from_name(&asset.name)
+            .ok_or_else(|| SelfUpdateError::Archive(asset.name.clone()))?;
+        let archive = replace::sibling(exe, ".download");
+        let targets = self.update_targets(exe);
+
+        let result = self
+            .download_verified(release, asset, &archive)
+            .and_then(|_| {
+                targets.iter().enumerate().try_for_each(|(index, target)| {
+                    let name = target
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .unwrap_or_default();
+                    let companion = index > 0;
+                    if companion && kind == download::ArchiveKind::Bare {
+                        return Err(SelfUpdateError::MissingCompanion {
+                            name: name.to_string(),
+                        });
+                    }
+                    download::extract(&archive, kind, name, &replace::sibling(target, suffix))
+                        .map_err(|err| match err {
+                            SelfUpdateError::Archive(_) if companion => {
+                                SelfUpdateError::MissingCompanion {
+                                    name: name.to_string(),
+                                }
+                            }
+                            err => err,
+                        })
+                })
+            });
+        // A failed download is kept so that the next attempt can resume it.
+        if !matches!(result, Err(SelfUpdateError::Download(_))) {
+            let _ = std::fs::remove_file(&archive);
+        }
+        if result.is_err() {
+            for target in &targets {
+                let _ = std::fs::remove_file(replace::sibling(target, suffix));
+            }
+        }
+        result
+    }
+
+    /// Moves each `<target><suffix>` over its target. If one of them can't be
+    /// replaced, those already replaced are restored.
+    fn replace_all(&self, exe: &Path, suffix: &str) -> Result<(), SelfUpdateError> {
+        let targets = self.update_targets(exe);
+        let mut result = Ok(());
+        for (index, target) in targets.iter().enumerate() {
+            if let Err(err) = replace::replace_executable(target, &replace::sibling(target, suffix))
+            {
+                for replaced in &targets[..index] {
+                    let _ = replace::restore(replaced);
+                }
+                result = Err(err.into());
+                break;
+            }
+        }
+        for target in &targets {
+            let _ = std::fs::remove_file(replace::sibling(target, suffix));
+        }
+        result
+    }
+
+    /// Downloads `asset` to `path` and checks its checksum, signature and
+    /// attestation as configured.
+    fn download_verified(
+        &self,
+        release: &crate::Release,
+        asset: &crate::Asset,
+        path: &Path,
+    ) -> Result<(), SelfUpdateError> {
+        let expected = checksum::expected_sha256(&self.tool_name, asset, &release.assets)?;
+        let trusted_key = self
+            .update_options
+            .signing_key
+            .as_deref()
+            .map(signature::parse_key)
+            .transpose()?;
+
+        download::download(&self.tool_name, asset, path, |downloaded, total| {
+            self.report(UpdateProgress::Downloading { downloaded, total })
+        })?;
+
+        self.report(UpdateProgress::Verifying);
+        checksum::verify(
+            path,
+            &asset.name,
+            expected.as_ref().map(|checksum| checksum.digest.as_str()),
+            self.update_options.require_checksum,
+        )?;
+        if let Some(ref key) = trusted_key {
+            signature::verify(
+                &self.tool_name,
+                key,
+                path,
+                asset,
+                expected.as_ref(),
+                &release.assets,
+            )?;
+        }
+        self.verify_attestation(path, asset, &release.assets)
+    }
+}
+
+#[cfg(feature = "self-update-download")]
+impl VersionChecker {
+    #[cfg(feature = "self-update-attestation")]
+    fn verify_attestation(
+        &self,
+        file: &Path,
+        asset: &crate::Asset,
+        assets: &[crate::Asset],
+    ) -> Result<(), SelfUpdateError> {
+        match self.update_options.attestation_repo {
+            Some(ref repo) => attestation::verify(&self.tool_name, repo, file, asset, assets),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "self-update-attestation"))]
+    fn verify_attestation(
+        &self,
+        _file: &Path,
+        _asset: &crate::Asset,
+        _assets: &[crate::Asset],
+    ) -> Result<(), SelfUpdateError> {
+        Ok(())
+    }
+}
+
+/// The command installing `version` of `tool` over `exe`. Cargo installs into
+/// the `bin` directory of its root, which is given as the one `exe` is in, so
+/// that an executable outside `~/.cargo/bin` is replaced rather than joined by
+/// a second copy.
+fn installer_command(tool: &str, version: &str, exe: &Path) -> Result<Command, SelfUpdateError> {
+    let spec = format!("{}@{}", tool, version);
+    let root = exe
+        .parent()
+        .filter(|dir| dir.file_name().is_some_and(|name| name == "bin"))
+        .and_then(Path::parent)
+        .ok_or_else(|| SelfUpdateError::OutsideInstallRoot {
+            exe: exe.to_path_buf(),
+        })?;
+
+    if let Some(binstall) = find_executable("cargo-binstall") {
+        let mut command = Command::new(binstall);
+        command.args(["binstall", "--no-confirm", "--root"]);
+        command.arg(root).arg(&spec);
+        return Ok(command);
+    }
+
+    let cargo = find_executable("cargo").ok_or(SelfUpdateError::NoInstaller)?;
+    let mut command = Command::new(cargo);
+    command.args(["install", "--locked", "--root"]);
+    command.arg(root).arg(&spec);
+    Ok(command)
+}
+
+/// The upgrade command of the package manager behind `method`, if it has one
+/// and it is on the `PATH`. Package names are assumed to match the tool's,
+/// except for apt, which is only used when dpkg says which package owns
+/// `exe`. `sudo` is run with `-n`, so that it fails rather than waits for a
+/// password when nobody is there to type it.
+fn package_manager_command(method: InstallMethod, tool: &str, exe: &Path) -> Option<Command> {
+    let (program, args): (&str, &[&str]) = match method {
+        InstallMethod::Homebrew => ("brew", &["upgrade"]),
+        InstallMethod::Scoop => ("scoop", &["update"]),
+        InstallMethod::Winget => ("winget", &["upgrade", "--exact"]),
+        InstallMethod::SystemPackage => ("apt-get", &["install", "--only-upgrade", "--yes"]),
+        _ => return None,
+    };
+    let program = find_executable(program)?;
+    let package = match method {
+        InstallMethod::SystemPackage => dpkg_owner(exe)?,
+        _ => tool.to_string(),
+    };
+
+    let mut command = match find_executable("sudo") {
+        Some(sudo) if method == InstallMethod::SystemPackage => {
+            let mut command = Command::new(sudo);
+            command.arg("-n").arg(program);
+            command
+        }
+        _ => Command::new(program),
+    };
+    command.args(args).arg(package);
+    Some(command)
+}
+
+/// The Debian package `exe` belongs to, as `dpkg -S` reports it, e.g.
+/// `ripgrep` from `ripgrep: /usr/bin/rg` or `foo` from `foo:amd64: ...`.
+/// With a merged `/usr`, packages may list `/usr/bin/foo` as `/bin/foo`.
+fn dpkg_owner(exe: &Path) -> Option<String> {
+    let dpkg = find_executable("dpkg")?;
+    let unmerged = exe
+        .strip_prefix("/usr")
+        .ok()
+        .map(|rest| Path::new("/").join(rest));
+    std::iter::once(exe)
+        .chain(unmerged.as_deref())
+        .find_map(|path| dpkg_search(&dpkg, path))
+}
+
+fn dpkg_search(dpkg: &Path, exe: &Path) -> Option<String> {
+    let output = Command::new(dpkg)
+        .arg("-S")
+        .arg(exe)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let listing = String::from_utf8(output.stdout).ok()?;
+    let (owner, _) = listing.lines().next()?.split_once(": ")?;
+    let package = owner.split(':').next()?.trim();
+    // Files diverted or shared by several packages are left alone.
+    (!package.is_empty() && !package.contains([',', ' '])).then(|| package.to_string())
+}
+
+/// The version in `found`, the output of `--version`, e.g. `1.2.0` from
+/// `my-tool 1.2.0 (abc123)`.
+fn reported_version(found: &str) -> Option<String> {
+    found
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| {
+            word.starts_with(|c: char| c.is_ascii_digit()) && crate::normalize_version(word).is_ok()
+        })
+        .map(str::to_string)
+}
+
+/// Runs `exe --version` and checks that `expected` appears in its output.
+pub(crate) fn verify_version(exe: &Path, expected: &str) -> Result<(), SelfUpdateError> {
+    let broken = |reason: String| SelfUpdateError::BrokenUpdate {
+        reason,
+        restored: false,
+    };
+
+    let mut child = Command::new(exe)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| broken(format!("could not start it ({})", err)))?;
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > VERIFY_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(broken(format!(
+                "`--version` did not exit within {} seconds",
+                VERIFY_TIMEOUT.as_secs()
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    if !status.success() {
+        return Err(broken(format!("`--version` failed ({})", status)));
+    }
+
+    let mut stdout = String::new();
+    if let Some(mut pipe) = child.stdout.take() {
+        let _ = pipe.read_to_string(&mut stdout);
+    }
+    let reported = stdout.trim().to_string();
+
+    if reported
+        .split_whitespace()
+        .any(|word| word.trim_start_matches('v') == expected)
+    {
+        Ok(())
+    } else {
+        Err(SelfUpdateError::VerificationFailed {
+            expected: expected.to_string(),
+            found: reported,
+            restored: false,
+        })
+    }
+}
+
+/// Turns an access-denied error on `exe`'s directory into
+/// [`SelfUpdateError::PermissionDenied`], which tells the user what to do.
+#[cfg(feature = "self-update-download")]
+fn permission_error(exe: &Path, err: std::io::Error) -> SelfUpdateError {
+    if err.kind() == std::io::ErrorKind::PermissionDenied {
+        SelfUpdateError::PermissionDenied {
+            dir: exe.parent().unwrap_or(exe).to_path_buf(),
+        }
+    } else {
+        err.into()
+    }
+}
+
+fn describe(command: &Command) -> String {
+    let program = PathBuf::from(command.get_program());
+    let name = program
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    std::iter::once(name)
+        .chain(
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned()),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Moves `new` over `exe`, keeping the previous executable as `<exe>.old` so
+/// that it can be [restored](restore).
+///
+/// On Unix the old file is hard-linked aside and a rename atomically replaces
+/// it, even while it is running. Windows refuses to overwrite a running
+/// executable but allows renaming it, so the old file is moved aside first.
+#[cfg(feature = "self-update-download")]
+pub(crate) fn replace_executable(exe: &Path, new: &Path) -> io::Result<()> {
+    if !exe.exists() {
+        return fs::rename(new, exe);
+    }
+    let old = clear_old(exe)?;
+
+    if cfg!(windows) {
+        fs::rename(exe, &old)?;
+        if let Err(err) = fs::rename(new, exe) {
+            let _ = fs::rename(&old, exe);
+            return Err(err);
+        }
+        Ok(())
+    } else {
+        if fs::hard_link(exe, &old).is_err() {
+            fs::copy(exe, &old)?;
+        }
+        fs::rename(new, exe)
+    }
+}
+
+/// Copies `exe` to `<exe>.old` before an installer overwrites it.
+pub(crate) fn backup(exe: &Path) -> io::Result<()> {
+    let old = clear_old(exe)?;
+    fs::copy(exe, &old).map(|_| ())
+}
+
+/// Checks that files can be created next to `exe`, which replacing it needs.
+pub(crate) fn ensure_writable(exe: &Path) -> io::Result<()> {
+    let probe = sibling(exe, ".probe");
+    fs::File::create(&probe)?;
+    fs::remove_file(&probe)
+}
+
+/// Makes room for a new `<exe>.old` and returns its path.
+///
+/// Windows won't delete an executable that is still running, here or in
+/// another process, but does allow renaming it: such a leftover is moved to
+/// `<exe>.old-<timestamp>` and removed by a later update once it is free.
+fn clear_old(exe: &Path) -> io::Result<PathBuf> {
+    let old = sibling(exe, ".old");
+    if old.exists() && fs::remove_file(&old).is_err() {
+        let stamp = crate::get_current_timestamp();
+        fs::rename(&old, sibling(exe, &format!(".old-{}", stamp)))?;
+    }
+
+    let prefix = sibling(exe, ".old-");
+    let prefix = prefix.file_name().unwrap_or_default().to_string_lossy();
+    if let Some(dir) = exe.parent() {
+        for entry in fs::read_dir(dir)?.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&*prefix) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+    Ok(old)
+}
+
+/// Moves `<exe>.old` back in place of `exe`. Returns `false` when there is no
+/// previous executable to restore.
+pub(crate) fn restore(exe: &Path) -> io::Result<bool> {
+    let old = sibling(exe, ".old");
+    if !old.is_file() {
+        return Ok(false);
+    }
+
+    if cfg!(windows) {
+        // The running executable can only be renamed, not replaced; the
+        // leftover is cleaned up the next time this runs.
+        let failed = sibling(exe, ".failed");
+        let _ = fs::remove_file(&failed);
+        fs::rename(exe, &failed)?;
+        if let Err(err) = fs::rename(&old, exe) {
+            let _ = fs::rename(&failed, exe);
+            return Err(err);
+        }
+    } else {
+        fs::rename(&old, exe)?;
+    }
+    Ok(true)
+}
+
+/// A path next to `exe` with `suffix` appended to its file name, so renames
+/// between the two stay on one filesystem.
+pub(crate) fn sibling(exe: &Path, suffix: &str) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    exe.with_file_name(name)
+}
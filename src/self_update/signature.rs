@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::Path;
+
+use minisign_verify::{PublicKey, Signature};
+
+use super::checksum::{fetch_text, Checksum};
+use super::SelfUpdateError;
+use crate::Asset;
+
+pub(crate) fn parse_key(base64: &str) -> Result<PublicKey, SelfUpdateError> {
+    PublicKey::from_base64(base64.trim())
+        .map_err(|err| SelfUpdateError::InvalidPublicKey(err.to_string()))
+}
+
+/// Checks the minisign signature of the downloaded `file`, or of the checksum
+/// file its SHA-256 was read from. Either is enough: once the checksum file is
+/// trusted, the digest check already ties the download to it.
+pub(crate) fn verify(
+    tool: &str,
+    key: &PublicKey,
+    file: &Path,
+    asset: &Asset,
+    checksum: Option<&Checksum>,
+    assets: &[Asset],
+) -> Result<(), SelfUpdateError> {
+    if let Some(signature) = find_signature(assets, &asset.name) {
+        let contents = fs::read(file)?;
+        return check(tool, key, &asset.name, &contents, signature);
+    }
+
+    let manifest = checksum.and_then(|checksum| checksum.manifest.as_ref());
+    if let Some(manifest) = manifest {
+        if let Some(signature) = find_signature(assets, &manifest.name) {
+            return check(
+                tool,
+                key,
+                &manifest.name,
+                manifest.contents.as_bytes(),
+                signature,
+            );
+        }
+    }
+
+    Err(SelfUpdateError::SignatureMissing {
+        asset: asset.name.clone(),
+    })
+}
+
+fn find_signature<'a>(assets: &'a [Asset], name: &str) -> Option<&'a Asset> {
+    let signature_name = format!("{}.minisig", name);
+    assets.iter().find(|asset| asset.name == signature_name)
+}
+
+fn check(
+    tool: &str,
+    key: &PublicKey,
+    name: &str,
+    contents: &[u8],
+    signature: &Asset,
+) -> Result<(), SelfUpdateError> {
+    let invalid = |reason: String| SelfUpdateError::SignatureInvalid {
+        file: name.to_string(),
+        reason,
+    };
+
+    let signature = Signature::decode(&fetch_text(tool, &signature.url)?)
+        .map_err(|err| invalid(err.to_string()))?;
+    key.verify(contents, &signature, false)
+        .map_err(|err| invalid(err.to_string()))
+}
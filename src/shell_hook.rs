@@ -0,0 +1,118 @@
+//! Update notices shown by the user's shell at startup, rather than in the
+//! middle of a tool's output.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A shell that [`shell_hook`] can write a snippet for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 4] = [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell];
+
+    /// The shell's usual name: `bash`, `zsh`, `fish` or `powershell`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::PowerShell => "powershell",
+        }
+    }
+
+    /// The shell called `name`, as returned by [`name`](Self::name). `pwsh`
+    /// is accepted as well.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pwsh" => Some(Shell::PowerShell),
+            name => Shell::ALL.into_iter().find(|shell| shell.name() == name),
+        }
+    }
+}
+
+/// A snippet to add to the startup file of `shell` (`~/.bashrc`,
+/// `~/.zshrc`, `~/.config/fish/config.fish` or `$PROFILE`), which prints
+/// the pending notice of every tool that has
+/// [shell notices](crate::VersionChecker::set_shell_notices) enabled. It only
+/// reads small text files, and starts no process.
+///
+/// ```
+/// use moz_cli_version_check::{shell_hook, Shell};
+///
+/// assert!(shell_hook(Shell::Bash).contains(".mozbuild/notices"));
+/// ```
+pub fn shell_hook(shell: Shell) -> String {
+    let snippet = match shell {
+        Shell::Bash => {
+            "for f in \"$HOME\"/.mozbuild/notices/*.txt; do if [ -s \"$f\" ]; then cat \"$f\"; fi; done\n"
+        }
+        Shell::Zsh => {
+            "for f in \"$HOME\"/.mozbuild/notices/*.txt(N); do if [ -s \"$f\" ]; then cat \"$f\"; fi; done\n"
+        }
+        Shell::Fish => "for f in $HOME/.mozbuild/notices/*.txt\n    test -s $f; and cat $f\nend\n",
+        Shell::PowerShell => {
+            "Get-ChildItem -Path (Join-Path $HOME '.mozbuild/notices') -Filter *.txt \
+             -ErrorAction SilentlyContinue | Where-Object Length -gt 0 | Get-Content\n"
+        }
+    };
+    format!("# Pending updates of Mozilla CLI tools\n{}", snippet)
+}
+
+/// Where the notice of `tool` is kept while it has shell notices enabled.
+fn notice_path(tool: &str) -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+        home.join(".mozbuild")
+            .join("notices")
+            .join(format!("{}.txt", tool))
+    })
+}
+
+/// Whether the user asked for the notices of `tool` to be shown by their
+/// shell.
+pub(crate) fn enabled(tool: &str) -> bool {
+    notice_path(tool).is_some_and(|path| path.exists())
+}
+
+/// Creates the notice file of `tool`, empty until an update is found, or
+/// removes it.
+pub(crate) fn set_enabled(tool: &str, enabled: bool) -> io::Result<()> {
+    let path = notice_path(tool)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    if !enabled {
+        return match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if !path.exists() {
+        fs::write(&path, "")?;
+    }
+    Ok(())
+}
+
+/// Replaces the pending notice of `tool`; an empty one clears it.
+pub(crate) fn write_notice(tool: &str, notice: &str) {
+    if let Some(path) = notice_path(tool) {
+        let _ = fs::write(path, notice);
+    }
+}
+
+/// Clears the pending notice of `tool`, if there is one.
+pub(crate) fn clear_notice(tool: &str) {
+    let pending = notice_path(tool)
+        .and_then(|path| fs::metadata(path).ok())
+        .is_some_and(|metadata| metadata.len() > 0);
+    if pending {
+        write_notice(tool, "");
+    }
+}
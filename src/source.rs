@@ -0,0 +1,1055 @@
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{http, json};
+use crate::{VersionOrdering, VersionScheme, CHECK_TIMEOUT_SECONDS};
+
+/// A downloadable file attached to a release, such as a prebuilt binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+    pub name: String,
+    pub url: String,
+    pub size: Option<u64>,
+    /// Hex-encoded SHA-256 of the file, when the source publishes it.
+    pub sha256: Option<String>,
+}
+
+/// A published release of a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    /// Canonical page describing the release, e.g. its crates.io version
+    /// page or GitHub release.
+    pub url: Option<String>,
+    /// Publication time, in seconds since the Unix epoch.
+    pub published_at: Option<u64>,
+    /// A short excerpt of the release notes.
+    pub notes: Option<String>,
+    /// Files attached to the release. Only needed for self-updates, so not
+    /// kept in the cache.
+    pub assets: Vec<Asset>,
+    /// Every version published so far, in any order, when the source can
+    /// list them. Used to tell how many releases the user is behind.
+    pub versions: Vec<String>,
+}
+
+impl Release {
+    pub fn new(version: impl Into<String>) -> Self {
+        Self {
+            version: version.into(),
+            url: None,
+            published_at: None,
+            notes: None,
+            assets: Vec::new(),
+            versions: Vec::new(),
+        }
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn with_published_at(mut self, published_at: u64) -> Self {
+        self.published_at = Some(published_at);
+        self
+    }
+
+    pub fn with_asset(mut self, asset: Asset) -> Self {
+        self.assets.push(asset);
+        self
+    }
+
+    pub fn with_versions(mut self, versions: impl IntoIterator<Item = String>) -> Self {
+        self.versions = versions.into_iter().collect();
+        self
+    }
+
+    /// Attaches release notes, keeping only a short excerpt of them.
+    pub fn with_notes(mut self, notes: &str) -> Self {
+        self.notes = notes_excerpt(notes);
+        self
+    }
+}
+
+/// Why a [`Source`] could not answer a question about a tool's releases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    /// The lookup failed, e.g. because the network is unavailable.
+    LookupFailed,
+    /// The source can't answer this question.
+    Unsupported,
+}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceError::LookupFailed => write!(f, "the release lookup failed"),
+            SourceError::Unsupported => write!(f, "the release source can't tell"),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+/// Where the latest release of a tool is looked up.
+pub trait Source: Send + Sync {
+    /// Looks up the latest release of `tool`. `None` means the lookup failed;
+    /// the attempt is still throttled like a successful one.
+    fn latest_release(&self, tool: &str) -> Option<Release>;
+
+    /// The canonical page for `version` of `tool`, for releases that were
+    /// not obtained through [`latest_release`](Self::latest_release).
+    fn release_url(&self, _tool: &str, _version: &str) -> Option<String> {
+        None
+    }
+
+    /// A short description of where releases come from, for status output.
+    fn describe(&self) -> String {
+        "custom source".to_string()
+    }
+
+    /// Whether `version` of `tool` was published and can still be installed.
+    /// By default only the latest release is known, so other versions are
+    /// [`SourceError::Unsupported`].
+    fn is_published(&self, tool: &str, version: &str) -> Result<bool, SourceError> {
+        let latest = self.latest_release(tool).ok_or(SourceError::LookupFailed)?;
+        if latest.version == version {
+            Ok(true)
+        } else {
+            Err(SourceError::Unsupported)
+        }
+    }
+
+    /// The releases of `tool` newer than `since`, newest first, with their
+    /// release notes in full, to review before updating. By default only the
+    /// latest release is known, with an excerpt of its notes at most, so it
+    /// is the only one returned.
+    fn releases_since(&self, tool: &str, since: &str) -> Result<Vec<Release>, SourceError> {
+        let latest = self.latest_release(tool).ok_or(SourceError::LookupFailed)?;
+        Ok(newer_than(since, vec![latest]))
+    }
+}
+
+/// Those of `releases` newer than `since`, newest first.
+fn newer_than(since: &str, releases: Vec<Release>) -> Vec<Release> {
+    let mut releases: Vec<Release> = releases
+        .into_iter()
+        .filter(|release| {
+            VersionScheme::Semver.compare(&release.version, since) == Ordering::Greater
+        })
+        .collect();
+    releases.sort_by(|a, b| VersionScheme::Semver.compare(&b.version, &a.version));
+    releases
+}
+
+/// The response to a GET of `url`, whatever its status.
+fn fetch(tool: &str, url: &str) -> Result<http::Response, SourceError> {
+    http_client(tool)
+        .get(url)
+        .send()
+        .map_err(|_| SourceError::LookupFailed)
+}
+
+/// Set to the URL of a server speaking the crates.io API, such as a
+/// `MockRegistry` in tests, to look crates up there.
+pub(crate) const REGISTRY_ENV: &str = "MOZTOOLS_UPDATE_CHECK_REGISTRY";
+
+/// Where [`CratesIo`] sends its API requests.
+fn crates_io_api() -> String {
+    match std::env::var(REGISTRY_ENV) {
+        Ok(url) if !url.is_empty() => url.trim_end_matches('/').to_string(),
+        _ => "https://crates.io".to_string(),
+    }
+}
+
+/// Looks up releases on crates.io, or on the registry
+/// `MOZTOOLS_UPDATE_CHECK_REGISTRY` names. This is the default source.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CratesIo {
+    reported: CratesIoVersion,
+}
+
+/// Which of the versions crates.io reports for a crate is its latest.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CratesIoVersion {
+    /// `max_stable_version`, the highest version that isn't a pre-release.
+    /// Crates that only published pre-releases fall back to `max_version`.
+    #[default]
+    MaxStable,
+    /// `max_version`, the highest version, pre-releases included.
+    Max,
+    /// `newest_version`, the most recently published version, whatever its
+    /// number: a `1.4.3` backport published after `2.0.0` is newer.
+    Newest,
+}
+
+impl CratesIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chooses which version crates.io reports counts as the latest.
+    /// Pre-releases are still only offered as configured on the checker.
+    pub fn reported_version(mut self, reported: CratesIoVersion) -> Self {
+        self.reported = reported;
+        self
+    }
+
+    /// The GitHub releases of `tool`'s repository, when its manifest says it
+    /// is on GitHub, for the release notes and assets crates.io doesn't
+    /// have.
+    pub fn github_releases(&self, tool: &str) -> Result<Option<GitHubReleases>, SourceError> {
+        let url = format!("{}/api/v1/crates/{}", crates_io_api(), tool);
+        let response: CratesIoResponse = fetch(tool, &url)?
+            .error_for_status()
+            .and_then(|response| response.json())
+            .map_err(|_| SourceError::LookupFailed)?;
+        let repository = response.crate_info.repository.as_deref();
+        Ok(repository
+            .and_then(github_repository)
+            .map(|(owner, repo)| GitHubReleases::new(owner, repo)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CratesIoResponse {
+    #[serde(rename = "crate")]
+    crate_info: CrateInfo,
+    #[serde(default)]
+    versions: Vec<CrateVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateInfo {
+    max_version: String,
+    /// Missing when the crate has only published pre-releases.
+    #[serde(default)]
+    max_stable_version: Option<String>,
+    #[serde(default)]
+    newest_version: Option<String>,
+    /// Where the crate's code lives, as its manifest says.
+    #[serde(default)]
+    repository: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateVersion {
+    num: String,
+    created_at: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateVersionResponse {
+    version: CrateVersion,
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for CratesIoResponse {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(CratesIoResponse {
+            crate_info: object.take("crate")?,
+            versions: object.take_or_default("versions")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for CrateInfo {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(CrateInfo {
+            max_version: object.take("max_version")?,
+            max_stable_version: object.take("max_stable_version")?,
+            newest_version: object.take("newest_version")?,
+            repository: object.take("repository")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for CrateVersion {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(CrateVersion {
+            num: object.take("num")?,
+            created_at: object.take("created_at")?,
+            yanked: object.take_or_default("yanked")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for CrateVersionResponse {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        Some(CrateVersionResponse {
+            version: json::Object::new(value)?.take("version")?,
+        })
+    }
+}
+
+impl Source for CratesIo {
+    #[cfg_attr(
+        feature = "test-util",
+        doc = r#"
+A yanked newest version isn't offered, a pre-release doesn't outrank the
+latest stable release, and a crate with only pre-releases reports the highest
+of them:
+
+```
+use moz_cli_version_check::{CrateFixture, CratesIo, MockRegistry, Source};
+
+let registry = MockRegistry::start().unwrap();
+registry.publish(CrateFixture::new("yanked").version("1.0.0").version("1.1.0").yanked("1.1.0"));
+registry.publish(CrateFixture::new("alpha").version("1.0.0").version("2.0.0-alpha.1"));
+registry.publish(CrateFixture::new("unstable").version("0.1.0-alpha.1").version("0.1.0-alpha.2"));
+registry.install();
+
+let source = CratesIo::new();
+let release = source.latest_release("yanked").unwrap();
+assert_eq!(release.version, "1.0.0");
+assert_eq!(release.versions, ["1.0.0"]);
+assert_eq!(source.latest_release("alpha").unwrap().version, "1.0.0");
+assert_eq!(source.latest_release("unstable").unwrap().version, "0.1.0-alpha.2");
+```
+"#
+    )]
+    fn latest_release(&self, tool: &str) -> Option<Release> {
+        let url = format!("{}/api/v1/crates/{}", crates_io_api(), tool);
+        let response: CratesIoResponse = http_client(tool).get(&url).send().ok()?.json().ok()?;
+        let versions: Vec<String> = response
+            .versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .map(|v| v.num.clone())
+            .collect();
+        let info = response.crate_info;
+        let reported = match self.reported {
+            // `max_version` is a pre-release as soon as one outranks the
+            // latest stable release, so it only serves for crates with no
+            // stable one.
+            CratesIoVersion::MaxStable => info.max_stable_version.or_else(|| {
+                VersionScheme::Semver
+                    .latest(&versions, false)
+                    .map(str::to_string)
+            }),
+            CratesIoVersion::Max => None,
+            CratesIoVersion::Newest => info.newest_version,
+        };
+        let version = reported.unwrap_or(info.max_version);
+        let url = self.release_url(tool, &version);
+        let published_at = response
+            .versions
+            .iter()
+            .find(|v| v.num == version)
+            .and_then(|v| parse_timestamp(&v.created_at));
+
+        Some(Release {
+            version,
+            url,
+            published_at,
+            notes: None,
+            assets: Vec::new(),
+            versions,
+        })
+    }
+
+    fn release_url(&self, tool: &str, version: &str) -> Option<String> {
+        Some(format!("https://crates.io/crates/{}/{}", tool, version))
+    }
+
+    fn describe(&self) -> String {
+        "crates.io".to_string()
+    }
+
+    /// The releases of the crate's repository, when it is on GitHub, since
+    /// crates.io has no release notes of its own.
+    fn releases_since(&self, tool: &str, since: &str) -> Result<Vec<Release>, SourceError> {
+        match self.github_releases(tool)? {
+            Some(github) => github.releases_since(tool, since),
+            None => {
+                let latest = self.latest_release(tool).ok_or(SourceError::LookupFailed)?;
+                Ok(newer_than(since, vec![latest]))
+            }
+        }
+    }
+
+    /// Yanked versions count as unpublished, since they can't be installed.
+    fn is_published(&self, tool: &str, version: &str) -> Result<bool, SourceError> {
+        let url = format!("{}/api/v1/crates/{}/{}", crates_io_api(), tool, version);
+        let response = fetch(tool, &url)?;
+        if response.status() == 404 {
+            return Ok(false);
+        }
+        let response: CrateVersionResponse = response
+            .error_for_status()
+            .and_then(|response| response.json())
+            .map_err(|_| SourceError::LookupFailed)?;
+        Ok(!response.version.yanked)
+    }
+}
+
+/// Looks up the latest release published on GitHub for `owner/repo`. A
+/// leading `v` is stripped from the tag name.
+#[derive(Debug, Clone)]
+pub struct GitHubReleases {
+    owner: String,
+    repo: String,
+}
+
+impl GitHubReleases {
+    pub fn new(owner: impl Into<String>, repo: impl Into<String>) -> Self {
+        Self {
+            owner: owner.into(),
+            repo: repo.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubReleaseTag {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+}
+
+/// The owner and name of a repository on GitHub, from its URL, such as
+/// `https://github.com/mozilla/searchfox-cli.git`.
+fn github_repository(url: &str) -> Option<(&str, &str)> {
+    let url = url.trim_end_matches('/');
+    let path = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let (owner, repo) = path.split_once('/')?;
+    let repo = repo.split('/').next()?.trim_end_matches(".git");
+    (!owner.is_empty() && !repo.is_empty()).then_some((owner, repo))
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    html_url: String,
+    published_at: Option<String>,
+    body: Option<String>,
+    #[serde(default)]
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+    size: Option<u64>,
+    /// `sha256:<hex>`, on releases uploaded after GitHub started recording it.
+    digest: Option<String>,
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for GitHubReleaseTag {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(GitHubReleaseTag {
+            tag_name: object.take("tag_name")?,
+            draft: object.take_or_default("draft")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for GitHubRelease {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(GitHubRelease {
+            tag_name: object.take("tag_name")?,
+            draft: object.take_or_default("draft")?,
+            prerelease: object.take_or_default("prerelease")?,
+            html_url: object.take("html_url")?,
+            published_at: object.take("published_at")?,
+            body: object.take("body")?,
+            assets: object.take_or_default("assets")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for GitHubAsset {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(GitHubAsset {
+            name: object.take("name")?,
+            browser_download_url: object.take("browser_download_url")?,
+            size: object.take("size")?,
+            digest: object.take("digest")?,
+        })
+    }
+}
+
+impl GitHubReleases {
+    /// The versions of the most recent hundred releases, or none if they
+    /// can't be listed.
+    fn versions(&self, tool: &str) -> Vec<String> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/releases?per_page=100",
+            self.owner, self.repo
+        );
+        let releases: Vec<GitHubReleaseTag> = http_client(tool)
+            .get(&url)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .and_then(http::Response::error_for_status)
+            .and_then(http::Response::json)
+            .unwrap_or_default();
+        releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .map(|release| release.tag_name.trim_start_matches('v').to_string())
+            .collect()
+    }
+}
+
+impl GitHubRelease {
+    fn into_release(self) -> Release {
+        Release {
+            version: self.tag_name.trim_start_matches('v').to_string(),
+            url: Some(self.html_url),
+            published_at: self.published_at.as_deref().and_then(parse_timestamp),
+            notes: self.body.as_deref().and_then(notes_excerpt),
+            assets: self
+                .assets
+                .into_iter()
+                .map(|asset| Asset {
+                    name: asset.name,
+                    url: asset.browser_download_url,
+                    size: asset.size,
+                    sha256: asset
+                        .digest
+                        .and_then(|d| d.strip_prefix("sha256:").map(str::to_string)),
+                })
+                .collect(),
+            versions: Vec::new(),
+        }
+    }
+}
+
+impl Source for GitHubReleases {
+    fn latest_release(&self, tool: &str) -> Option<Release> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/releases/latest",
+            self.owner, self.repo
+        );
+        let response: GitHubRelease = http_client(tool)
+            .get(&url)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .ok()?
+            .error_for_status()
+            .ok()?
+            .json()
+            .ok()?;
+
+        Some(Release {
+            versions: self.versions(tool),
+            ..response.into_release()
+        })
+    }
+
+    /// The most recent hundred releases, pre-releases and drafts left out.
+    fn releases_since(&self, tool: &str, since: &str) -> Result<Vec<Release>, SourceError> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/releases?per_page=100",
+            self.owner, self.repo
+        );
+        let releases: Vec<GitHubRelease> = http_client(tool)
+            .get(&url)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .and_then(http::Response::error_for_status)
+            .and_then(http::Response::json)
+            .map_err(|_| SourceError::LookupFailed)?;
+        let releases = releases
+            .into_iter()
+            .filter(|release| !release.draft && !release.prerelease)
+            .map(|release| {
+                let notes = release
+                    .body
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|body| !body.is_empty())
+                    .map(str::to_string);
+                Release {
+                    notes,
+                    ..release.into_release()
+                }
+            })
+            .collect();
+        Ok(newer_than(since, releases))
+    }
+
+    fn release_url(&self, _tool: &str, version: &str) -> Option<String> {
+        Some(format!(
+            "https://github.com/{}/{}/releases/tag/v{}",
+            self.owner, self.repo, version
+        ))
+    }
+
+    fn describe(&self) -> String {
+        format!("GitHub releases of {}/{}", self.owner, self.repo)
+    }
+
+    /// Looks for a release tagged `v<version>` or `<version>`.
+    fn is_published(&self, tool: &str, version: &str) -> Result<bool, SourceError> {
+        for tag in [format!("v{}", version), version.to_string()] {
+            let url = format!(
+                "https://api.github.com/repos/{}/{}/releases/tags/{}",
+                self.owner, self.repo, tag
+            );
+            let response = fetch(tool, &url)?;
+            if response.is_success() {
+                return Ok(true);
+            }
+            if response.status() != 404 {
+                return Err(SourceError::LookupFailed);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// The Taskcluster deployment used for Firefox CI, whose index `mach
+/// bootstrap` downloads toolchains from.
+const FIREFOX_CI_ROOT_URL: &str = "https://firefox-ci-tc.services.mozilla.com";
+
+/// Looks up releases of tools distributed through Mozilla's build
+/// infrastructure, from a release manifest published as a Taskcluster
+/// artifact or on archive.mozilla.org.
+///
+/// The manifest is a JSON file such as:
+///
+/// ```json
+/// {
+///   "version": "1.4.0",
+///   "published_at": "2024-05-01T12:00:00Z",
+///   "url": "https://example.com/my-tool/1.4.0",
+///   "notes": "Faster symbolication.",
+///   "assets": [
+///     {
+///       "name": "my-tool-x86_64-unknown-linux-gnu.tar.gz",
+///       "url": "my-tool-x86_64-unknown-linux-gnu.tar.gz",
+///       "sha256": "…"
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Only `version` is required. Asset URLs may be relative to the manifest.
+#[derive(Debug, Clone)]
+pub struct MozillaArtifacts {
+    manifest_url: String,
+}
+
+impl MozillaArtifacts {
+    /// Reads the manifest at `manifest_url`, e.g.
+    /// `https://archive.mozilla.org/pub/my-tool/latest/manifest.json`.
+    pub fn new(manifest_url: impl Into<String>) -> Self {
+        Self {
+            manifest_url: manifest_url.into(),
+        }
+    }
+
+    /// Reads the `artifact` (e.g. `public/build/manifest.json`) of the task
+    /// indexed at `namespace` on Firefox CI, e.g.
+    /// `gecko.v2.mozilla-central.latest.my-tool.linux64`.
+    pub fn taskcluster(namespace: &str, artifact: &str) -> Self {
+        Self::new(format!(
+            "{}/api/index/v1/task/{}/artifacts/{}",
+            FIREFOX_CI_ROOT_URL, namespace, artifact
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReleaseManifest {
+    version: String,
+    published_at: Option<String>,
+    url: Option<String>,
+    notes: Option<String>,
+    #[serde(default)]
+    assets: Vec<ManifestAsset>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestAsset {
+    name: String,
+    url: String,
+    size: Option<u64>,
+    sha256: Option<String>,
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for ReleaseManifest {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(ReleaseManifest {
+            version: object.take("version")?,
+            published_at: object.take("published_at")?,
+            url: object.take("url")?,
+            notes: object.take("notes")?,
+            assets: object.take_or_default("assets")?,
+            versions: object.take_or_default("versions")?,
+        })
+    }
+}
+
+#[cfg(feature = "tiny")]
+impl json::FromJson for ManifestAsset {
+    fn from_json(value: serde_json::Value) -> Option<Self> {
+        let mut object = json::Object::new(value)?;
+        Some(ManifestAsset {
+            name: object.take("name")?,
+            url: object.take("url")?,
+            size: object.take("size")?,
+            sha256: object.take("sha256")?,
+        })
+    }
+}
+
+impl ReleaseManifest {
+    /// Converts the manifest to a [`Release`], resolving relative asset URLs
+    /// against `base`, the URL of the manifest's directory.
+    pub(crate) fn into_release(self, base: &str) -> Release {
+        Release {
+            version: self.version.trim_start_matches('v').to_string(),
+            url: self.url,
+            published_at: self.published_at.as_deref().and_then(parse_timestamp),
+            notes: self.notes.as_deref().and_then(notes_excerpt),
+            assets: self
+                .assets
+                .into_iter()
+                .map(|asset| Asset {
+                    url: if asset.url.contains("://") {
+                        asset.url
+                    } else {
+                        format!("{}/{}", base.trim_end_matches('/'), asset.url)
+                    },
+                    name: asset.name,
+                    size: asset.size,
+                    sha256: asset.sha256,
+                })
+                .collect(),
+            versions: self.versions,
+        }
+    }
+}
+
+impl Source for MozillaArtifacts {
+    fn latest_release(&self, tool: &str) -> Option<Release> {
+        let manifest: ReleaseManifest = http_client(tool)
+            .get(&self.manifest_url)
+            .send()
+            .ok()?
+            .error_for_status()
+            .ok()?
+            .json()
+            .ok()?;
+
+        let base = self
+            .manifest_url
+            .rsplit_once('/')
+            .map_or(self.manifest_url.as_str(), |(base, _)| base);
+        Some(manifest.into_release(base))
+    }
+
+    fn describe(&self) -> String {
+        format!("Mozilla artifacts at {}", self.manifest_url)
+    }
+}
+
+/// Reports releases from a local manifest listing several tools, such as one
+/// written by `moz-version-check export-manifest` on a connected machine,
+/// for machines that can't reach the registry:
+///
+/// ```json
+/// {
+///   "my-tool": {
+///     "version": "1.4.2",
+///     "published_at": "2024-05-01T10:20:30Z",
+///     "versions": ["1.4.0", "1.4.1", "1.4.2"]
+///   },
+///   "other-tool": { "version": "0.9.0" }
+/// }
+/// ```
+///
+/// Each entry is a manifest as [`MozillaArtifacts`] reads it, with the
+/// versions published so far if known. Asset URLs may be relative to the
+/// file's directory.
+#[derive(Debug, Clone)]
+pub struct FileManifest {
+    path: PathBuf,
+}
+
+impl FileManifest {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The manifest `FileManifest` reads, listing each of `releases` under
+    /// the name of its tool.
+    ///
+    /// ```
+    /// use moz_cli_version_check::{FileManifest, Release, Source};
+    ///
+    /// let release = Release::new("1.4.2").with_published_at(1714558830);
+    /// let manifest = FileManifest::render(&[("my-tool".to_string(), release.clone())]);
+    /// let path = std::env::temp_dir().join("file-manifest-example.json");
+    /// std::fs::write(&path, manifest).unwrap();
+    /// assert_eq!(FileManifest::new(&path).latest_release("my-tool"), Some(release));
+    /// ```
+    pub fn render(releases: &[(String, Release)]) -> String {
+        let manifest: serde_json::Map<String, serde_json::Value> = releases
+            .iter()
+            .map(|(tool, release)| {
+                let mut entry = serde_json::json!({ "version": release.version });
+                if let Some(ref url) = release.url {
+                    entry["url"] = url.clone().into();
+                }
+                if let Some(published_at) = release.published_at {
+                    entry["published_at"] = format_timestamp(published_at).into();
+                }
+                if let Some(ref notes) = release.notes {
+                    entry["notes"] = notes.clone().into();
+                }
+                if !release.assets.is_empty() {
+                    entry["assets"] = release
+                        .assets
+                        .iter()
+                        .map(|asset| {
+                            serde_json::json!({
+                                "name": asset.name,
+                                "url": asset.url,
+                                "size": asset.size,
+                                "sha256": asset.sha256,
+                            })
+                        })
+                        .collect();
+                }
+                if !release.versions.is_empty() {
+                    entry["versions"] = release.versions.clone().into();
+                }
+                (tool.clone(), entry)
+            })
+            .collect();
+        let manifest = serde_json::Value::Object(manifest);
+        serde_json::to_string_pretty(&manifest).unwrap_or_default() + "\n"
+    }
+}
+
+impl Source for FileManifest {
+    fn latest_release(&self, tool: &str) -> Option<Release> {
+        let content = std::fs::read_to_string(&self.path).ok()?;
+        let mut manifests: HashMap<String, ReleaseManifest> = json::from_str(&content).ok()?;
+        let base = self.path.parent().map(|dir| dir.display().to_string());
+        Some(
+            manifests
+                .remove(tool)?
+                .into_release(base.as_deref().unwrap_or(".")),
+        )
+    }
+
+    fn describe(&self) -> String {
+        format!("release manifest in {}", self.path.display())
+    }
+}
+
+/// Reports the version of each tool a team has standardized on, from a JSON
+/// policy file or feed mapping tool names to versions:
+///
+/// ```json
+/// {
+///   "my-tool": "1.4.2",
+///   "other-tool": "0.9.0"
+/// }
+/// ```
+///
+/// Meant for [`VersionChecker::blessed`](crate::VersionChecker::blessed),
+/// which reports any other version as a mismatch.
+#[derive(Debug, Clone)]
+pub struct BlessedVersions {
+    location: PolicyLocation,
+}
+
+#[derive(Debug, Clone)]
+enum PolicyLocation {
+    File(PathBuf),
+    Url(String),
+}
+
+impl BlessedVersions {
+    /// Reads the policy from a local file, e.g. one kept in a team's
+    /// configuration repository.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            location: PolicyLocation::File(path.into()),
+        }
+    }
+
+    /// Fetches the policy from `url`.
+    pub fn url(url: impl Into<String>) -> Self {
+        Self {
+            location: PolicyLocation::Url(url.into()),
+        }
+    }
+}
+
+impl Source for BlessedVersions {
+    fn latest_release(&self, tool: &str) -> Option<Release> {
+        let mut versions: HashMap<String, String> = match self.location {
+            PolicyLocation::File(ref path) => {
+                json::from_str(&std::fs::read_to_string(path).ok()?).ok()?
+            }
+            PolicyLocation::Url(ref url) => http_client(tool)
+                .get(url)
+                .send()
+                .ok()?
+                .error_for_status()
+                .ok()?
+                .json()
+                .ok()?,
+        };
+        let version = versions.remove(tool)?;
+        Some(Release::new(version.trim_start_matches('v')))
+    }
+
+    fn describe(&self) -> String {
+        match self.location {
+            PolicyLocation::File(ref path) => format!("blessed versions in {}", path.display()),
+            PolicyLocation::Url(ref url) => format!("blessed versions at {}", url),
+        }
+    }
+}
+
+pub(crate) fn http_client(tool: &str) -> http::Client {
+    http::Client::new(
+        format!("{}/version-check", tool),
+        Some(Duration::from_secs(CHECK_TIMEOUT_SECONDS)),
+    )
+    .rate_limited()
+}
+
+const NOTES_EXCERPT_LINES: usize = 5;
+const NOTES_EXCERPT_LINE_WIDTH: usize = 100;
+
+/// The first few non-empty lines of `notes`, each cut to a reasonable width.
+fn notes_excerpt(notes: &str) -> Option<String> {
+    let lines: Vec<String> = notes
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(NOTES_EXCERPT_LINES)
+        .map(
+            |line| match line.char_indices().nth(NOTES_EXCERPT_LINE_WIDTH) {
+                Some((end, _)) => format!("{}…", &line[..end]),
+                None => line.to_string(),
+            },
+        )
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Parses an RFC 3339 timestamp such as `2024-05-01T10:20:30.123+02:00` or
+/// `2024-05-01T10:20:30Z` into seconds since the Unix epoch. Fields out of
+/// range, such as a month 13 or a year past 9999, make it `None` rather than
+/// overflow the arithmetic below.
+pub(crate) fn parse_timestamp(value: &str) -> Option<u64> {
+    let (date, time) = value.split_once(['T', ' '])?;
+    let mut date_parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (
+        date_parts.next()??,
+        date_parts.next()??,
+        date_parts.next()??,
+    );
+    if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let offset_start = time.find(['Z', 'z', '+', '-']).unwrap_or(time.len());
+    let (clock, offset) = time.split_at(offset_start);
+    let clock = clock.split('.').next()?;
+    let mut clock_parts = clock.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (
+        clock_parts.next()??,
+        clock_parts.next()??,
+        clock_parts.next()??,
+    );
+    // 60 is a leap second.
+    if !(0..24).contains(&hour) || !(0..60).contains(&minute) || !(0..=60).contains(&second) {
+        return None;
+    }
+
+    let offset_seconds = match offset.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let (h, m) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+            let (h, m) = (h.parse::<i64>().ok()?, m.parse::<i64>().ok()?);
+            if !(0..24).contains(&h) || !(0..60).contains(&m) {
+                return None;
+            }
+            let seconds = h * 3600 + m * 60;
+            if sign == '+' {
+                seconds
+            } else {
+                -seconds
+            }
+        }
+        _ => 0,
+    };
+
+    // Days from civil, after Howard Hinnant's algorithm.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let timestamp = days * 86400 + hour * 3600 + minute * 60 + second - offset_seconds;
+    u64::try_from(timestamp).ok()
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 timestamp in UTC,
+/// such as `2024-05-01T10:20:30Z`, which [`parse_timestamp`] reads back.
+pub(crate) fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86400, timestamp % 86400);
+
+    // Civil from days, after Howard Hinnant's algorithm.
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,177 @@
+//! Where the cache is kept: `~/.mozbuild/tool-versions.json`, unless a
+//! [`CacheStore`] was set with [`set_cache_store`], or each tool's entry in
+//! `~/.mozbuild/version-check/<tool>.json` with [`set_cache_per_tool`].
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Keeps the cache somewhere other than `~/.mozbuild/tool-versions.json`,
+/// for hosts without a home directory or file system, such as WASI
+/// components and browsers.
+///
+/// ```
+/// use moz_cli_version_check::{set_cache_store, CacheStore};
+/// use std::sync::Mutex;
+///
+/// #[derive(Default)]
+/// struct InMemory(Mutex<Option<String>>);
+///
+/// impl CacheStore for InMemory {
+///     fn load(&self) -> Option<String> {
+///         self.0.lock().unwrap().clone()
+///     }
+///
+///     fn save(&self, contents: &str) {
+///         *self.0.lock().unwrap() = Some(contents.to_string());
+///     }
+/// }
+///
+/// set_cache_store(InMemory::default());
+/// ```
+pub trait CacheStore: Send + Sync {
+    /// The contents last saved, or `None` if there are none yet.
+    fn load(&self) -> Option<String>;
+
+    /// Replaces the contents. Failures can be ignored: without a cache,
+    /// lookups just happen more often.
+    fn save(&self, contents: &str);
+}
+
+static STORE: RwLock<Option<Arc<dyn CacheStore>>> = RwLock::new(None);
+
+/// Keeps the cache in `store` from now on, for the whole process.
+pub fn set_cache_store(store: impl CacheStore + 'static) {
+    *STORE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(store));
+}
+
+fn registered() -> Option<Arc<dyn CacheStore>> {
+    STORE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Whether a [`CacheStore`] keeps the cache instead of the files.
+pub(crate) fn is_custom() -> bool {
+    registered().is_some()
+}
+
+static PER_TOOL: AtomicBool = AtomicBool::new(false);
+
+/// Keeps each tool's entry in its own file, `~/.mozbuild/version-check/<tool>.json`,
+/// from now on, for the whole process. Checks then only read and write the
+/// file of their tool, so tools checking at the same time don't contend for
+/// the combined `~/.mozbuild/tool-versions.json`; an entry still found only
+/// there is read from it, and moves to its own file the next time it is
+/// saved. Has no effect once a [`CacheStore`] is set.
+pub fn set_cache_per_tool(enabled: bool) {
+    PER_TOOL.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether entries are saved in their own files.
+pub(crate) fn per_tool() -> bool {
+    PER_TOOL.load(Ordering::Relaxed) && registered().is_none()
+}
+
+pub(crate) fn tools_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".mozbuild").join("version-check"))
+}
+
+fn tool_path(tool_name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}.json", tool_name.replace(['/', '\\'], "_"));
+    tools_dir().map(|dir| dir.join(file_name))
+}
+
+/// The contents of the file of `tool_name`, if entries are kept per tool
+/// and it has one.
+pub(crate) fn load_tool(tool_name: &str) -> Option<String> {
+    if !per_tool() {
+        return None;
+    }
+    load_tool_file(tool_name)
+}
+
+/// The contents of the file of `tool_name`, if it has one, whether or not
+/// this process keeps entries per tool.
+pub(crate) fn load_tool_file(tool_name: &str) -> Option<String> {
+    if registered().is_some() {
+        return None;
+    }
+    fs::read_to_string(tool_path(tool_name)?).ok()
+}
+
+pub(crate) fn save_tool(tool_name: &str, contents: &str) {
+    if let Some(path) = tool_path(tool_name) {
+        write(&path, contents);
+    }
+}
+
+/// The name and contents of every per-tool file, whether or not this process
+/// keeps entries per tool.
+pub(crate) fn load_tools() -> Vec<(String, String)> {
+    if registered().is_some() {
+        return Vec::new();
+    }
+    let Some(entries) = tools_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let tool = path.file_stem()?.to_str()?.to_string();
+            Some((tool, fs::read_to_string(&path).ok()?))
+        })
+        .collect()
+}
+
+/// Deletes the file of `tool_name`, returning whether it had one.
+pub(crate) fn remove_tool(tool_name: &str) -> bool {
+    registered().is_none() && tool_path(tool_name).is_some_and(|path| fs::remove_file(path).is_ok())
+}
+
+/// Empties the cache, per-tool files included.
+pub(crate) fn clear() {
+    if let Some(store) = registered() {
+        return store.save("{}");
+    }
+    if let Some(cache_path) = cache_path() {
+        let _ = fs::remove_file(cache_path);
+    }
+    if let Some(dir) = tools_dir() {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+pub(crate) fn cache_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".mozbuild").join("tool-versions.json"))
+}
+
+pub(crate) fn load() -> Option<String> {
+    if let Some(store) = registered() {
+        return store.load();
+    }
+    let cache_path = cache_path()?;
+    if !cache_path.exists() {
+        return None;
+    }
+    fs::read_to_string(&cache_path).ok()
+}
+
+pub(crate) fn save(contents: &str) {
+    if let Some(store) = registered() {
+        return store.save(contents);
+    }
+    if let Some(cache_path) = cache_path() {
+        write(&cache_path, contents);
+    }
+}
+
+fn write(path: &Path, contents: &str) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::File::create(path).and_then(|mut file| file.write_all(contents.as_bytes()));
+}
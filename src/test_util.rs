@@ -0,0 +1,353 @@
+//! A stand-in for crates.io that tools can run in their own tests, to see
+//! their update notices without reaching the network.
+//!
+//! [`MockRegistry`] serves the crates.io API from a local port, answering
+//! with the crates [`CrateFixture`] describes. [`CratesIo`](crate::CratesIo)
+//! is pointed at it with `MOZTOOLS_UPDATE_CHECK_REGISTRY`, in the test
+//! process or in the tool's binary the test runs.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::source::{format_timestamp, REGISTRY_ENV};
+use crate::{VersionOrdering, VersionScheme};
+
+/// A version of a [`CrateFixture`].
+#[derive(Debug, Clone)]
+struct VersionFixture {
+    num: String,
+    published_at: u64,
+    yanked: bool,
+}
+
+/// A crate as crates.io describes it, built up version by version, for
+/// [`MockRegistry::publish`] or to feed a [`Source`](crate::Source) under
+/// test directly.
+///
+/// ```
+/// use moz_cli_version_check::CrateFixture;
+///
+/// let json = CrateFixture::new("my-tool")
+///     .version("1.0.0")
+///     .version("1.1.0")
+///     .version("2.0.0-beta.1")
+///     .to_json();
+/// let response: serde_json::Value = serde_json::from_str(&json).unwrap();
+/// assert_eq!(response["crate"]["max_stable_version"], "1.1.0");
+/// assert_eq!(response["crate"]["max_version"], "2.0.0-beta.1");
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrateFixture {
+    name: String,
+    versions: Vec<VersionFixture>,
+    repository: Option<String>,
+}
+
+impl CrateFixture {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            versions: Vec::new(),
+            repository: None,
+        }
+    }
+
+    /// Adds a version, published a day after the one added before it, so
+    /// that the last one added is the newest.
+    pub fn version(self, num: impl Into<String>) -> Self {
+        let published_at = self
+            .versions
+            .last()
+            .map_or(1_700_000_000, |last| last.published_at + 86400);
+        self.version_at(num, published_at)
+    }
+
+    /// Adds a version published at `published_at`, in seconds since the Unix
+    /// epoch, e.g. to test how recent releases are held back.
+    pub fn version_at(mut self, num: impl Into<String>, published_at: u64) -> Self {
+        self.versions.push(VersionFixture {
+            num: num.into(),
+            published_at,
+            yanked: false,
+        });
+        self
+    }
+
+    /// Marks `num` as yanked: crates.io still lists it, but it isn't offered
+    /// nor counts as published.
+    pub fn yanked(mut self, num: &str) -> Self {
+        for version in &mut self.versions {
+            if version.num == num {
+                version.yanked = true;
+            }
+        }
+        self
+    }
+
+    /// Sets the repository the crate's manifest gives, where its release
+    /// notes and assets are looked for.
+    pub fn repository(mut self, url: impl Into<String>) -> Self {
+        self.repository = Some(url.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The response to `GET /api/v1/crates/<name>`, with the highest and most
+    /// recent versions worked out the way crates.io does, yanked ones left
+    /// out.
+    pub fn to_json(&self) -> String {
+        let listed: Vec<String> = self
+            .versions
+            .iter()
+            .filter(|version| !version.yanked)
+            .map(|version| version.num.clone())
+            .collect();
+        let semver = VersionScheme::Semver;
+        let newest = self
+            .versions
+            .iter()
+            .filter(|version| !version.yanked)
+            .max_by_key(|version| version.published_at)
+            .map(|version| version.num.clone());
+        let value = json!({
+            "crate": {
+                "name": self.name,
+                "max_version": semver.latest(&listed, true).unwrap_or("0.0.0"),
+                "max_stable_version": semver.latest(&listed, false),
+                "newest_version": newest,
+                "repository": self.repository,
+            },
+            // Newest first, as crates.io lists them.
+            "versions": self
+                .versions
+                .iter()
+                .rev()
+                .map(version_value)
+                .collect::<Vec<_>>(),
+        });
+        value.to_string()
+    }
+
+    /// The response to `GET /api/v1/crates/<name>/<num>`, if the crate has
+    /// that version.
+    pub fn version_json(&self, num: &str) -> Option<String> {
+        let version = self.versions.iter().find(|version| version.num == num)?;
+        Some(json!({ "version": version_value(version) }).to_string())
+    }
+}
+
+fn version_value(version: &VersionFixture) -> Value {
+    json!({
+        "num": version.num,
+        "created_at": format_timestamp(version.published_at),
+        "yanked": version.yanked,
+    })
+}
+
+/// What the registry answers for a path.
+#[derive(Debug, Clone)]
+struct Reply {
+    status: u16,
+    body: String,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    replies: HashMap<String, Reply>,
+    requests: Vec<String>,
+    delay: Duration,
+}
+
+/// An HTTP server on a local port answering like the crates.io API, for the
+/// crates published on it, until dropped. Anything else is a 404.
+///
+/// ```
+/// use moz_cli_version_check::{CrateFixture, CratesIo, MockRegistry, Source};
+///
+/// let registry = MockRegistry::start().unwrap();
+/// registry.publish(CrateFixture::new("my-tool").version("1.0.0").version("1.2.0"));
+/// registry.install();
+///
+/// let release = CratesIo::new().latest_release("my-tool").unwrap();
+/// assert_eq!(release.version, "1.2.0");
+/// assert_eq!(registry.requests(), ["/api/v1/crates/my-tool"]);
+/// ```
+///
+/// To test a tool's binary, pass it [`env`](Self::env) instead, along with a
+/// `HOME` of its own so that it starts with an empty cache.
+pub struct MockRegistry {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl MockRegistry {
+    /// Starts serving on a free port of `127.0.0.1`.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let server = {
+            let (state, stopped) = (state.clone(), stopped.clone());
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let state = state.clone();
+                    thread::spawn(move || {
+                        let _ = serve(stream, &state);
+                    });
+                }
+            })
+        };
+        Ok(Self {
+            addr,
+            state,
+            stopped,
+            server: Some(server),
+        })
+    }
+
+    /// The registry's URL, such as `http://127.0.0.1:41234`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// The environment variable pointing [`CratesIo`](crate::CratesIo) at
+    /// this registry, and its value, to pass to a tool's binary with
+    /// `Command::env`.
+    pub fn env(&self) -> (&'static str, String) {
+        (REGISTRY_ENV, self.url())
+    }
+
+    /// Points [`CratesIo`](crate::CratesIo) at this registry for the rest of
+    /// the test process, for tests that check in-process.
+    pub fn install(&self) {
+        std::env::set_var(REGISTRY_ENV, self.url());
+    }
+
+    /// Serves `fixture`, replacing what was published under its name.
+    pub fn publish(&self, fixture: CrateFixture) {
+        let path = format!("/api/v1/crates/{}", fixture.name);
+        let versions = format!("{}/", path);
+        let mut state = self.lock();
+        state
+            .replies
+            .retain(|other, _| other != &path && !other.starts_with(&versions));
+        for version in &fixture.versions {
+            if let Some(body) = fixture.version_json(&version.num) {
+                let reply = Reply { status: 200, body };
+                state
+                    .replies
+                    .insert(format!("{}/{}", path, version.num), reply);
+            }
+        }
+        let body = fixture.to_json();
+        state.replies.insert(path, Reply { status: 200, body });
+    }
+
+    /// Answers every request about `name` with `status` and an error, as
+    /// crates.io does when it is down or rate limiting, e.g. 503 or 429.
+    pub fn fail(&self, name: &str, status: u16) {
+        let path = format!("/api/v1/crates/{}", name);
+        let versions = format!("{}/", path);
+        let body = json!({ "errors": [{ "detail": "mock failure" }] }).to_string();
+        let mut state = self.lock();
+        state
+            .replies
+            .retain(|other, _| !other.starts_with(&versions));
+        state.replies.insert(path, Reply { status, body });
+    }
+
+    /// Waits `delay` before answering each request, to see how a tool
+    /// copes with a slow registry. Lookups give up after 5 seconds.
+    pub fn delay(&self, delay: Duration) {
+        self.lock().delay = delay;
+    }
+
+    /// The paths requested so far, in order, to tell whether a check looked
+    /// anything up or answered from the cache.
+    pub fn requests(&self) -> Vec<String> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockRegistry {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the server up so that it sees it was stopped.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+/// Answers the one request `stream` sends, then closes it.
+fn serve(mut stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+    let mut words = request_line.split_whitespace();
+    let method = words.next().unwrap_or_default();
+    let path = words.next().unwrap_or_default();
+    // Query strings such as `?include=` don't change the answer.
+    let path = path.split('?').next().unwrap_or_default().to_string();
+
+    let (reply, delay) = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.requests.push(path.clone());
+        (state.replies.get(&path).cloned(), state.delay)
+    };
+    let reply = reply.unwrap_or_else(|| Reply {
+        status: 404,
+        body: json!({ "errors": [{ "detail": "Not Found" }] }).to_string(),
+    });
+    thread::sleep(delay);
+
+    let reason = match reply.status {
+        200 => "OK",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Mock",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        reply.status,
+        reason,
+        reply.body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(reply.body.as_bytes())?;
+    }
+    stream.flush()
+}
@@ -0,0 +1,594 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use semver::Version;
+
+use crate::Bump;
+
+/// A version after [`normalize_version`]'s normalization: a semver version,
+/// plus the components past the third that semver has no room for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Parsed {
+    pub(crate) version: Version,
+    pub(crate) extra: Vec<u64>,
+}
+
+impl fmt::Display for Parsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = &self.version;
+        write!(f, "{}.{}.{}", v.major, v.minor, v.patch)?;
+        for component in &self.extra {
+            write!(f, ".{}", component)?;
+        }
+        if !v.pre.is_empty() {
+            write!(f, "-{}", v.pre)?;
+        }
+        if !v.build.is_empty() {
+            write!(f, "+{}", v.build)?;
+        }
+        Ok(())
+    }
+}
+
+/// A version string that can't be read, such as a `git describe` output
+/// given where a version was expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionParseError {
+    pub version: String,
+}
+
+impl fmt::Display for VersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a version that can be compared",
+            self.version
+        )
+    }
+}
+
+impl std::error::Error for VersionParseError {}
+
+/// Normalizes a version string the way tools report it, failing when there
+/// is no version to find in it.
+///
+/// - surrounding whitespace, and anything after the version, such as the
+///   commit in `1.2.3 (abc123)`, is dropped;
+/// - a leading `v` or `V` is dropped;
+/// - missing minor or patch components count as zero, so `1.2` is `1.2.0`;
+/// - components past the third are kept and compared, so `1.2.3.4` is newer
+///   than `1.2.3` and older than `1.2.3.10`;
+/// - a pre-release (`-beta.1`) or build metadata (`+abc`) suffix is kept.
+///
+/// Versions are compared by semver precedence once normalized.
+///
+/// ```
+/// use moz_cli_version_check::normalize_version;
+///
+/// assert_eq!(normalize_version(" v1.2 ").unwrap(), "1.2.0");
+/// assert_eq!(normalize_version("1.2.3 (abc123 2024-06-01)").unwrap(), "1.2.3");
+/// assert_eq!(normalize_version("1.2.3.4").unwrap(), "1.2.3.4");
+/// assert_eq!(normalize_version("V2.0.0-rc.1+build5").unwrap(), "2.0.0-rc.1+build5");
+/// assert!(normalize_version("1.x").is_err());
+/// assert!(normalize_version("heads/main-0-gabc123").is_err());
+/// ```
+pub fn normalize_version(version: &str) -> Result<String, VersionParseError> {
+    parse(version)
+        .map(|parsed| parsed.to_string())
+        .ok_or_else(|| VersionParseError {
+            version: version.to_string(),
+        })
+}
+
+/// Versions longer than this are not versions but garbage, possibly hostile,
+/// from a registry; they are never parsed.
+const MAX_VERSION_LEN: usize = 128;
+
+/// How many numeric components are read at most.
+const MAX_COMPONENTS: usize = 16;
+
+/// `version` without surrounding whitespace, or nothing when it is too long
+/// to be a version.
+fn bounded(version: &str) -> &str {
+    let version = version.trim();
+    if version.len() > MAX_VERSION_LEN {
+        ""
+    } else {
+        version
+    }
+}
+
+pub(crate) fn parse(version: &str) -> Option<Parsed> {
+    let version = bounded(version)
+        .split_whitespace()
+        .next()?
+        .trim_start_matches(['v', 'V']);
+
+    let core_end = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(core_end);
+    let components = core
+        .split('.')
+        .map(str::parse)
+        .collect::<Result<Vec<u64>, _>>()
+        .ok()?;
+    if components.len() > MAX_COMPONENTS {
+        return None;
+    }
+    let component = |i: usize| components.get(i).copied().unwrap_or(0);
+
+    let padded = format!(
+        "{}.{}.{}{}",
+        component(0),
+        component(1),
+        component(2),
+        suffix
+    );
+    // Padding can grow a version past the limit; reject it so a normalized
+    // version always parses again.
+    if padded.len() > MAX_VERSION_LEN {
+        return None;
+    }
+    // The numeric core was checked above; this validates the suffix.
+    let version = Version::parse(&padded).ok()?;
+    Some(Parsed {
+        version,
+        extra: components.get(3..).unwrap_or_default().to_vec(),
+    })
+}
+
+/// The version a development build is based on, with its markers removed,
+/// or `None` when `version` doesn't look like a development build.
+///
+/// A pre-release identifier starting with `dev` (`1.2.3-dev`, `1.2.3-rc.1.dev.4`)
+/// or containing `dirty` (`1.2.3-4-gabc123-dirty`) marks one, along with
+/// build metadata starting with `git` (`1.2.3+git.abc123`) or containing
+/// `dirty`. The pre-release is cut at the first marker, and build metadata
+/// with a marker is dropped.
+pub(crate) fn dev_build_base(version: &str) -> Option<String> {
+    let mut parsed = parse(version)?;
+    let is_marker = |id: &str| {
+        let id = id.to_ascii_lowercase();
+        id.starts_with("dev") || id.contains("dirty")
+    };
+
+    let pre = parsed.version.pre.as_str().to_string();
+    let kept = pre
+        .split('.')
+        .take_while(|id| !is_marker(id))
+        .collect::<Vec<_>>();
+    let pre_marked = !pre.is_empty() && kept.len() < pre.split('.').count();
+    let build = parsed.version.build.as_str().to_ascii_lowercase();
+    let build_marked = build.starts_with("git") || build.contains("dirty");
+    if !pre_marked && !build_marked {
+        return None;
+    }
+
+    if pre_marked {
+        parsed.version.pre = semver::Prerelease::new(&kept.join(".")).ok()?;
+    }
+    if build_marked {
+        parsed.version.build = semver::BuildMetadata::EMPTY;
+    }
+    Some(parsed.to_string())
+}
+
+/// Orders two normalized versions: numeric components first, including any
+/// past the third, then the pre-release. Build metadata is ignored, as semver
+/// requires, so `1.2.3+build5` and `1.2.3` are equal.
+fn compare_parsed(a: &Parsed, b: &Parsed) -> Ordering {
+    let core = |p: &Parsed| (p.version.major, p.version.minor, p.version.patch);
+    let len = a.extra.len().max(b.extra.len());
+    let extra = |p: &Parsed| {
+        (0..len)
+            .map(|i| p.extra.get(i).copied().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    core(a)
+        .cmp(&core(b))
+        .then_with(|| extra(a).cmp(&extra(b)))
+        .then_with(|| a.version.cmp_precedence(&b.version))
+}
+
+/// Orders two versions by [`compare_parsed`]. When either can't be
+/// normalized, their numeric components are compared instead, and a version
+/// without any is equal to everything.
+fn compare(a: &str, b: &str) -> Ordering {
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => compare_parsed(&a, &b),
+        _ => {
+            let (a, b) = (numeric_components(a), numeric_components(b));
+            if a.is_empty() || b.is_empty() {
+                return Ordering::Equal;
+            }
+            a.cmp(&b)
+        }
+    }
+}
+
+fn numeric_components(version: &str) -> Vec<u64> {
+    bounded(version)
+        .trim_start_matches('v')
+        .split('.')
+        .filter_map(|s| s.parse().ok())
+        .take(MAX_COMPONENTS)
+        .collect()
+}
+
+fn is_prerelease(version: &str) -> bool {
+    parse(version).is_some_and(|p| !p.version.pre.is_empty())
+}
+
+/// Decides which of two version strings is newer, for tools whose versions
+/// don't follow semver. Set with
+/// [`VersionChecker::version_ordering`](crate::VersionChecker::version_ordering).
+///
+/// Closures taking two versions and returning their [`Ordering`] implement
+/// it:
+///
+/// ```
+/// use moz_cli_version_check::VersionChecker;
+///
+/// // Build numbers such as `r1234`.
+/// let checker = VersionChecker::new("my-tool", "r1234").version_ordering(|a: &str, b: &str| {
+///     let number = |v: &str| v.trim_start_matches('r').parse::<u64>().unwrap_or(0);
+///     number(a).cmp(&number(b))
+/// });
+/// ```
+pub trait VersionOrdering: Send + Sync {
+    fn compare(&self, a: &str, b: &str) -> Ordering;
+
+    /// Checks that `version` can be ordered. Anything can, by default.
+    fn validate(&self, version: &str) -> Result<(), VersionParseError> {
+        let _ = version;
+        Ok(())
+    }
+
+    /// Whether `version` is a pre-release, which is only offered to users who
+    /// opted in. Nothing is, by default.
+    fn is_prerelease(&self, version: &str) -> bool {
+        let _ = version;
+        false
+    }
+
+    /// How far `latest` is from `current`. By default, a change in the first
+    /// numeric component is a [`Bump::Major`], in the second a
+    /// [`Bump::Minor`], and anything else a [`Bump::Patch`].
+    fn bump(&self, current: &str, latest: &str) -> Bump {
+        positional_bump(&numeric_components(current), &numeric_components(latest))
+    }
+
+    /// The newest of `versions`, skipping pre-releases unless `prereleases`
+    /// and any version that doesn't [`validate`](Self::validate). Sources
+    /// that list every version, yanked ones removed, can use it rather than
+    /// trusting a registry's idea of the latest one.
+    ///
+    /// ```
+    /// use moz_cli_version_check::{VersionOrdering, VersionScheme};
+    ///
+    /// let versions: Vec<String> = ["1.9.0", "2.0.0-alpha.3", "1.10.0", "v1.10.0", "nightly"]
+    ///     .into_iter()
+    ///     .map(String::from)
+    ///     .collect();
+    /// let semver = VersionScheme::Semver;
+    /// assert_eq!(semver.latest(&versions, false), Some("1.10.0"));
+    /// assert_eq!(semver.latest(&versions, true), Some("2.0.0-alpha.3"));
+    /// assert_eq!(semver.latest(&versions[1..2], false), None);
+    /// assert_eq!(semver.latest(&[], true), None);
+    /// ```
+    fn latest<'a>(&self, versions: &'a [String], prereleases: bool) -> Option<&'a str> {
+        versions
+            .iter()
+            .map(String::as_str)
+            .filter(|v| self.validate(v).is_ok() && (prereleases || !self.is_prerelease(v)))
+            // The first of equal versions wins, so `1.2.0` is kept over
+            // a later `v1.2.0`.
+            .fold(None, |newest, v| match newest {
+                Some(newest) if self.compare(v, newest) != Ordering::Greater => Some(newest),
+                _ => Some(v),
+            })
+    }
+}
+
+impl<F> VersionOrdering for F
+where
+    F: Fn(&str, &str) -> Ordering + Send + Sync,
+{
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// The orderings shipped with this crate, chosen per checker with
+/// [`VersionChecker::version_scheme`](crate::VersionChecker::version_scheme).
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use moz_cli_version_check::{VersionOrdering, VersionScheme};
+///
+/// let semver = VersionScheme::Semver;
+/// assert_eq!(semver.compare("1.2.3+build5", "1.2.3"), Ordering::Equal);
+/// assert_eq!(semver.compare("1.2.3+build5", "v1.2.3+build6"), Ordering::Equal);
+/// assert_eq!(semver.compare("1.2.3-beta.1", "1.2.3"), Ordering::Less);
+/// assert_eq!(semver.compare("1.2.4+build1", "1.2.3+build9"), Ordering::Greater);
+///
+/// assert_eq!(VersionScheme::CalVer.compare("2024.10", "2024.6"), Ordering::Greater);
+/// assert_eq!(VersionScheme::CalVer.compare("2024.06+ci.7", "2024.6"), Ordering::Equal);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionScheme {
+    /// Semantic versioning, after [`normalize_version`].
+    #[default]
+    Semver,
+    /// Calendar versioning such as `2024.06.1` or `2024-06-01`: `.`, `-`
+    /// and `_` all separate components, each compared as an integer, so
+    /// `2024.10` is newer than `2024.6`. Text after the numbers
+    /// (`2024.06-beta`) marks a pre-release, and build metadata after a `+`
+    /// is ignored. A change of year is a [`Bump::Major`], of the second
+    /// component a [`Bump::Minor`], and anything later a [`Bump::Patch`].
+    CalVer,
+    /// Dot-separated integers compared left to right, with missing ones
+    /// counted as zero and anything that isn't a number ignored.
+    Numeric,
+}
+
+impl VersionOrdering for VersionScheme {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            VersionScheme::Semver => compare(a, b),
+            VersionScheme::CalVer => compare_calver(a, b),
+            VersionScheme::Numeric => {
+                let (a, b) = (numeric_components(a), numeric_components(b));
+                let component = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+                (0..a.len().max(b.len()))
+                    .map(|i| component(&a, i).cmp(&component(&b, i)))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            }
+        }
+    }
+
+    fn validate(&self, version: &str) -> Result<(), VersionParseError> {
+        let valid = match self {
+            VersionScheme::Semver => parse(version).is_some(),
+            VersionScheme::CalVer => !parse_calver(version).0.is_empty(),
+            VersionScheme::Numeric => !numeric_components(version).is_empty(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(VersionParseError {
+                version: version.to_string(),
+            })
+        }
+    }
+
+    fn is_prerelease(&self, version: &str) -> bool {
+        match self {
+            VersionScheme::Semver => is_prerelease(version),
+            VersionScheme::CalVer => !parse_calver(version).1.is_empty(),
+            VersionScheme::Numeric => false,
+        }
+    }
+
+    fn bump(&self, current: &str, latest: &str) -> Bump {
+        match self {
+            VersionScheme::Semver => version_bump(current, latest),
+            VersionScheme::CalVer => {
+                positional_bump(&parse_calver(current).0, &parse_calver(latest).0)
+            }
+            VersionScheme::Numeric => {
+                positional_bump(&numeric_components(current), &numeric_components(latest))
+            }
+        }
+    }
+}
+
+fn positional_bump(current: &[u64], latest: &[u64]) -> Bump {
+    let component = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    match (0..current.len().max(latest.len()))
+        .find(|&i| component(current, i) != component(latest, i))
+    {
+        Some(0) => Bump::Major,
+        Some(1) => Bump::Minor,
+        _ => Bump::Patch,
+    }
+}
+
+/// A calendar version: its numeric components, then any text after them.
+fn parse_calver(version: &str) -> (Vec<u64>, &str) {
+    let version = bounded(version)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(['v', 'V']);
+    // Build metadata doesn't take part in the ordering.
+    let version = version.split('+').next().unwrap_or_default();
+    let mut components = Vec::new();
+    let mut rest = version;
+    loop {
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let Ok(component) = rest[..end].parse() else {
+            break;
+        };
+        components.push(component);
+        rest = &rest[end..];
+        if components.len() == MAX_COMPONENTS {
+            break;
+        }
+        match rest.strip_prefix(['.', '-', '_']) {
+            Some(next) if next.starts_with(|c: char| c.is_ascii_digit()) => rest = next,
+            _ => break,
+        }
+    }
+    (components, rest.trim_start_matches(['.', '-', '_']))
+}
+
+fn compare_calver(a: &str, b: &str) -> Ordering {
+    let (a_parts, a_suffix) = parse_calver(a);
+    let (b_parts, b_suffix) = parse_calver(b);
+    let len = a_parts.len().max(b_parts.len());
+    let component = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| component(&a_parts, i).cmp(&component(&b_parts, i)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| match (a_suffix.is_empty(), b_suffix.is_empty()) {
+            (true, true) => Ordering::Equal,
+            // A suffix marks a pre-release, which comes first.
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a_suffix.cmp(b_suffix),
+        })
+}
+
+/// Which newer versions are offered as updates, set through
+/// [`VersionChecker`](crate::VersionChecker) builder methods.
+#[derive(Clone)]
+pub(crate) struct VersionPolicy {
+    pub(crate) ordering: Arc<dyn VersionOrdering>,
+    pub(crate) prereleases: bool,
+    /// The source reports the one version users should run, which is
+    /// offered whatever it is.
+    pub(crate) pinned: bool,
+    /// The versions the user pinned the tool to in their configuration,
+    /// the only ones offered.
+    pub(crate) requirement: Option<semver::VersionReq>,
+}
+
+impl Default for VersionPolicy {
+    fn default() -> Self {
+        VersionPolicy {
+            ordering: Arc::new(VersionScheme::Semver),
+            prereleases: false,
+            pinned: false,
+            requirement: None,
+        }
+    }
+}
+
+impl VersionPolicy {
+    /// Whether `latest` is newer than `current`. When either can't be read
+    /// by the ordering, any other version counts as newer, rather than
+    /// trusting an arbitrary comparison.
+    pub(crate) fn is_newer(&self, current: &str, latest: &str) -> bool {
+        // An empty latest version just means none is known yet.
+        if latest.trim().is_empty() {
+            return false;
+        }
+        let readable = [current, latest]
+            .iter()
+            .all(|version| self.ordering.validate(version).is_ok());
+        if readable {
+            self.ordering.compare(latest, current) == Ordering::Greater
+        } else {
+            bounded(latest) != bounded(current)
+        }
+    }
+
+    /// Whether `latest` should be offered to users of `current`. Pre-releases
+    /// are only offered when opted into, or to users already running one.
+    pub(crate) fn is_update(&self, current: &str, latest: &str) -> bool {
+        self.is_newer(current, latest) && self.offers(current, latest)
+    }
+
+    /// Whether `version` may be offered to users of `current` at all, newer
+    /// or not.
+    pub(crate) fn offers(&self, current: &str, version: &str) -> bool {
+        self.allows(version)
+            && (self.pinned
+                || self.prereleases_allowed(current)
+                || !self.ordering.is_prerelease(version))
+    }
+
+    /// Whether the user's pin allows `version`. Versions it can't read as
+    /// semver are allowed, rather than silently never offered.
+    fn allows(&self, version: &str) -> bool {
+        match (&self.requirement, parse(version)) {
+            (Some(requirement), Some(parsed)) => requirement.matches(&parsed.version),
+            _ => true,
+        }
+    }
+
+    pub(crate) fn is_prerelease(&self, version: &str) -> bool {
+        self.ordering.is_prerelease(version)
+    }
+
+    fn prereleases_allowed(&self, current: &str) -> bool {
+        self.prereleases || self.ordering.is_prerelease(current)
+    }
+
+    /// The newest of `versions` to offer users of `current`, with the same
+    /// rules for pre-releases as [`is_update`](Self::is_update).
+    pub(crate) fn latest<'a>(&self, current: &str, versions: &'a [String]) -> Option<&'a str> {
+        if self.requirement.is_some() {
+            let allowed: Vec<String> = versions
+                .iter()
+                .filter(|version| self.allows(version))
+                .cloned()
+                .collect();
+            let latest = self
+                .ordering
+                .latest(&allowed, self.prereleases_allowed(current))?;
+            return versions.iter().map(String::as_str).find(|v| *v == latest);
+        }
+        self.ordering
+            .latest(versions, self.prereleases_allowed(current))
+    }
+
+    /// How many of `versions` are stable releases newer than `current`, up to
+    /// and including `latest`; `None` when the source listed no versions.
+    pub(crate) fn versions_behind(
+        &self,
+        current: &str,
+        latest: &str,
+        versions: &[String],
+    ) -> Option<usize> {
+        if versions.is_empty() {
+            return None;
+        }
+        let mut counted: Vec<&str> = versions
+            .iter()
+            .map(String::as_str)
+            .filter(|v| {
+                !self.ordering.is_prerelease(v)
+                    && self.is_newer(current, v)
+                    && !self.is_newer(latest, v)
+            })
+            .collect();
+        // `1.2.0` and `v1.2.0` are the same release.
+        counted.sort_by(|a, b| self.ordering.compare(a, b));
+        counted.dedup_by(|a, b| self.ordering.compare(a, b).is_eq());
+        Some(counted.len())
+    }
+
+    /// How far `latest` is from `current`.
+    pub(crate) fn bump(&self, current: &str, latest: &str) -> Bump {
+        self.ordering.bump(current, latest)
+    }
+}
+
+fn version_bump(current: &str, latest: &str) -> Bump {
+    let components = |v: &str| match parse(v) {
+        Some(p) => vec![p.version.major, p.version.minor, p.version.patch],
+        None => numeric_components(v),
+    };
+
+    let current_parts = components(current);
+    let latest_parts = components(latest);
+    let component = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+
+    // The first component that differs decides the bump, but leading zeros
+    // shift the meaning down: in 0.x.y the minor component is the breaking one.
+    let leading_zeros = (0..2)
+        .take_while(|&i| component(&current_parts, i) == 0 && component(&latest_parts, i) == 0)
+        .count();
+
+    match (0..3).find(|&i| component(&current_parts, i) != component(&latest_parts, i)) {
+        Some(i) if i <= leading_zeros => Bump::Major,
+        Some(i) if i == leading_zeros + 1 => Bump::Minor,
+        _ => Bump::Patch,
+    }
+}